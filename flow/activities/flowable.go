@@ -251,10 +251,15 @@ func (a *FlowableActivity) CreateNormalizedTable(
 	for _, tableMapping := range config.TableMappings {
 		tableIdentifier := tableMapping.DestinationTableIdentifier
 		tableSchema := tableNameSchemaMapping[tableIdentifier]
+		tableConfig := config
+		if tableMapping.SoftDeleteColName != "" {
+			tableConfig = proto.CloneOf(config)
+			tableConfig.SoftDeleteColName = tableMapping.SoftDeleteColName
+		}
 		existing, err := conn.SetupNormalizedTable(
 			ctx,
 			tx,
-			config,
+			tableConfig,
 			tableIdentifier,
 			tableSchema,
 		)
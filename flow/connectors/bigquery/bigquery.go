@@ -1,6 +1,7 @@
 package connbigquery
 
 import (
+	"cmp"
 	"context"
 	"errors"
 	"fmt"
@@ -416,7 +417,7 @@ func (c *BigQueryConnector) NormalizeRecords(ctx context.Context, req *model.Nor
 
 	for batchId := normBatchID + 1; batchId <= req.SyncBatchID; batchId++ {
 		if err := c.mergeTablesInThisBatch(ctx, batchId,
-			req.FlowJobName, rawTableName, req.TableNameSchemaMapping, unchangedToastMergeChunking,
+			req.FlowJobName, rawTableName, req.TableNameSchemaMapping, req.TableMappings, unchangedToastMergeChunking,
 			&protos.PeerDBColumns{SoftDeleteColName: req.SoftDeleteColName, SyncedAtColName: req.SyncedAtColName},
 		); err != nil {
 			return model.NormalizeResponse{}, err
@@ -449,6 +450,7 @@ func (c *BigQueryConnector) mergeTablesInThisBatch(
 	flowName string,
 	rawTableName string,
 	tableToSchema map[string]*protos.TableSchema,
+	tableMappings []*protos.TableMapping,
 	unchangedToastMergeChunking uint32,
 	peerdbColumns *protos.PeerDBColumns,
 ) error {
@@ -479,11 +481,14 @@ func (c *BigQueryConnector) mergeTablesInThisBatch(
 		},
 		tableSchemaMapping: tableToSchema,
 		mergeBatchId:       batchId,
-		peerdbCols:         peerdbColumns,
 		shortColumn:        map[string]string{},
 	}
 
 	for _, tableName := range tableNames {
+		mergeGen.peerdbCols = &protos.PeerDBColumns{
+			SoftDeleteColName: utils.SoftDeleteColName(tableMappings, tableName, peerdbColumns.SoftDeleteColName),
+			SyncedAtColName:   peerdbColumns.SyncedAtColName,
+		}
 		unchangedToastColumns := tableNametoUnchangedToastCols[tableName]
 		dstDatasetTable, err := c.convertToDatasetTable(tableName)
 		if err != nil {
@@ -760,6 +765,7 @@ func (c *BigQueryConnector) RenameTables(
 ) (*protos.RenameTablesOutput, error) {
 	// BigQuery doesn't really do transactions properly anyway so why bother?
 	for _, renameRequest := range req.RenameTableOptions {
+		softDeleteColName := cmp.Or(renameRequest.SoftDeleteColName, req.SoftDeleteColName)
 		srcDatasetTable, _ := c.convertToDatasetTable(renameRequest.CurrentName)
 		dstDatasetTable, _ := c.convertToDatasetTable(renameRequest.NewName)
 		c.logger.Info(fmt.Sprintf("renaming table '%s' to '%s'...", srcDatasetTable.string(),
@@ -798,7 +804,7 @@ func (c *BigQueryConnector) RenameTables(
 				columnIsJSON[quotedCol] = (col.Type == "json" || col.Type == "jsonb")
 			}
 
-			if req.SoftDeleteColName != "" {
+			if softDeleteColName != "" {
 				allColsBuilder := strings.Builder{}
 				for idx, col := range columnNames {
 					allColsBuilder.WriteString("_pt.")
@@ -849,8 +855,8 @@ func (c *BigQueryConnector) RenameTables(
 					pkeyOnClauseBuilder.String(), ljWhereClauseBuilder.String())
 
 				q := fmt.Sprintf("INSERT INTO %s(%s) SELECT %s,true AS %s FROM %s _pt %s",
-					srcDatasetTable.string(), fmt.Sprintf("%s,%s", allColsWithoutAlias, req.SoftDeleteColName),
-					allColsWithAlias, req.SoftDeleteColName, dstDatasetTable.string(),
+					srcDatasetTable.string(), fmt.Sprintf("%s,%s", allColsWithoutAlias, softDeleteColName),
+					allColsWithAlias, softDeleteColName, dstDatasetTable.string(),
 					leftJoin)
 
 				query := c.queryWithLogging(q)
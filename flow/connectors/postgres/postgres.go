@@ -1,6 +1,7 @@
 package connpostgres
 
 import (
+	"cmp"
 	"context"
 	"crypto/tls"
 	"errors"
@@ -694,15 +695,15 @@ func (c *PostgresConnector) NormalizeRecords(
 		rawTableName:             rawTableIdentifier,
 		tableSchemaMapping:       req.TableNameSchemaMapping,
 		unchangedToastColumnsMap: unchangedToastColumnsMap,
-		peerdbCols: &protos.PeerDBColumns{
-			SoftDeleteColName: req.SoftDeleteColName,
-			SyncedAtColName:   req.SyncedAtColName,
-		},
-		supportsMerge:  pgversion >= shared.POSTGRES_15,
-		metadataSchema: c.metadataSchema,
+		supportsMerge:            pgversion >= shared.POSTGRES_15,
+		metadataSchema:           c.metadataSchema,
 	}
 
 	for _, destinationTableName := range destinationTableNames {
+		normalizeStmtGen.peerdbCols = &protos.PeerDBColumns{
+			SoftDeleteColName: utils.SoftDeleteColName(req.TableMappings, destinationTableName, req.SoftDeleteColName),
+			SyncedAtColName:   req.SyncedAtColName,
+		}
 		normalizeStatements := normalizeStmtGen.generateNormalizeStatements(destinationTableName)
 		for _, normalizeStatement := range normalizeStatements {
 			ct, err := normalizeRecordsTx.Exec(ctx, normalizeStatement, normBatchID, req.SyncBatchID, destinationTableName)
@@ -1599,6 +1600,7 @@ func (c *PostgresConnector) RenameTables(
 	defer shared.RollbackTx(renameTablesTx, c.logger)
 
 	for _, renameRequest := range req.RenameTableOptions {
+		softDeleteColName := cmp.Or(renameRequest.SoftDeleteColName, req.SoftDeleteColName)
 		srcTable, err := utils.ParseSchemaTable(renameRequest.CurrentName)
 		if err != nil {
 			return nil, fmt.Errorf("unable to parse source %s: %w", renameRequest.CurrentName, err)
@@ -1629,7 +1631,7 @@ func (c *PostgresConnector) RenameTables(
 
 		if originalTableExists {
 			tableSchema := tableNameSchemaMapping[renameRequest.CurrentName]
-			if req.SoftDeleteColName != "" {
+			if softDeleteColName != "" {
 				columnNames := make([]string, 0, len(tableSchema.Columns))
 				for _, col := range tableSchema.Columns {
 					columnNames = append(columnNames, utils.QuoteIdentifier(col.Name))
@@ -1651,7 +1653,7 @@ func (c *PostgresConnector) RenameTables(
 					fmt.Sprintf(
 						"INSERT INTO %s(%s) SELECT %s,true AS %s FROM %s original_table "+
 							"WHERE NOT EXISTS (SELECT 1 FROM %s resync_table WHERE %s)",
-						src, fmt.Sprintf("%s,%s", allCols, utils.QuoteIdentifier(req.SoftDeleteColName)), allCols, req.SoftDeleteColName,
+						src, fmt.Sprintf("%s,%s", allCols, utils.QuoteIdentifier(softDeleteColName)), allCols, softDeleteColName,
 						dst, src, pkeyColCompareStr), renameTablesTx)
 				if err != nil {
 					return nil, fmt.Errorf("unable to handle soft-deletes for table %s: %w", dst, err)
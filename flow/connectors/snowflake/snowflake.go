@@ -1,6 +1,7 @@
 package connsnowflake
 
 import (
+	"cmp"
 	"context"
 	"database/sql"
 	"encoding/json"
@@ -480,7 +481,7 @@ func (c *SnowflakeConnector) NormalizeRecords(ctx context.Context, req *model.No
 	for batchId := normBatchID + 1; batchId <= req.SyncBatchID; batchId++ {
 		c.logger.Info(fmt.Sprintf("normalizing records for batch %d [of %d]", batchId, req.SyncBatchID))
 		mergeErr := c.mergeTablesForBatch(ctx, batchId,
-			req.FlowJobName, req.Env, req.TableNameSchemaMapping,
+			req.FlowJobName, req.Env, req.TableNameSchemaMapping, req.TableMappings,
 			&protos.PeerDBColumns{
 				SoftDeleteColName: req.SoftDeleteColName,
 				SyncedAtColName:   req.SyncedAtColName,
@@ -507,6 +508,7 @@ func (c *SnowflakeConnector) mergeTablesForBatch(
 	flowName string,
 	env map[string]string,
 	tableToSchema map[string]*protos.TableSchema,
+	tableMappings []*protos.TableMapping,
 	peerdbCols *protos.PeerDBColumns,
 ) error {
 	destinationTableNames, err := c.getDistinctTableNamesInBatch(ctx, flowName, batchId, tableToSchema)
@@ -527,19 +529,22 @@ func (c *SnowflakeConnector) mergeTablesForBatch(
 	}
 	g.SetLimit(int(mergeParallelism))
 
-	mergeGen := &mergeStmtGenerator{
-		rawTableName:             getRawTableIdentifier(flowName),
-		mergeBatchId:             batchId,
-		tableSchemaMapping:       tableToSchema,
-		unchangedToastColumnsMap: tableNameToUnchangedToastCols,
-		peerdbCols:               peerdbCols,
-	}
-
 	for _, tableName := range destinationTableNames {
 		if gCtx.Err() != nil {
 			break
 		}
 
+		mergeGen := &mergeStmtGenerator{
+			rawTableName:             getRawTableIdentifier(flowName),
+			mergeBatchId:             batchId,
+			tableSchemaMapping:       tableToSchema,
+			unchangedToastColumnsMap: tableNameToUnchangedToastCols,
+			peerdbCols: &protos.PeerDBColumns{
+				SoftDeleteColName: utils.SoftDeleteColName(tableMappings, tableName, peerdbCols.SoftDeleteColName),
+				SyncedAtColName:   peerdbCols.SyncedAtColName,
+			},
+		}
+
 		g.Go(func() error {
 			mergeStatement, err := mergeGen.generateMergeStmt(gCtx, env, tableName)
 			if err != nil {
@@ -731,6 +736,7 @@ func (c *SnowflakeConnector) RenameTables(
 	}()
 
 	for _, renameRequest := range req.RenameTableOptions {
+		softDeleteColName := cmp.Or(renameRequest.SoftDeleteColName, req.SoftDeleteColName)
 		srcTable, err := utils.ParseSchemaTable(renameRequest.CurrentName)
 		if err != nil {
 			return nil, fmt.Errorf("unable to parse source %s: %w", renameRequest.CurrentName, err)
@@ -767,7 +773,7 @@ func (c *SnowflakeConnector) RenameTables(
 		}
 
 		if originalTableExists {
-			if req.SoftDeleteColName != "" {
+			if softDeleteColName != "" {
 				tableSchema := tableNameSchemaMapping[renameRequest.CurrentName]
 				columnNames := make([]string, 0, len(tableSchema.Columns))
 				for _, col := range tableSchema.Columns {
@@ -786,7 +792,7 @@ func (c *SnowflakeConnector) RenameTables(
 
 				_, err = c.execWithLoggingTx(ctx,
 					fmt.Sprintf("INSERT INTO %s(%s) SELECT %s,true AS %s FROM %s WHERE (%s) NOT IN (SELECT %s FROM %s)",
-						src, fmt.Sprintf("%s,%s", allCols, req.SoftDeleteColName), allCols, req.SoftDeleteColName,
+						src, fmt.Sprintf("%s,%s", allCols, softDeleteColName), allCols, softDeleteColName,
 						dst, pkeyCols, pkeyCols, src), renameTablesTx)
 				if err != nil {
 					return nil, fmt.Errorf("unable to handle soft-deletes for table %s: %w", dst, err)
@@ -1,6 +1,7 @@
 package utils
 
 import (
+	"cmp"
 	"fmt"
 	"time"
 
@@ -155,6 +156,17 @@ func InitialiseTableRowsMap(tableMaps []*protos.TableMapping) map[string]*model.
 	return tableNameRowsMapping
 }
 
+// SoftDeleteColName is the soft delete column of a destination table,
+// the table mapping of the table can replace the soft delete column of the mirror
+func SoftDeleteColName(tableMaps []*protos.TableMapping, destinationTable string, softDeleteColName string) string {
+	for _, mapping := range tableMaps {
+		if mapping.DestinationTableIdentifier == destinationTable {
+			return cmp.Or(mapping.SoftDeleteColName, softDeleteColName)
+		}
+	}
+	return softDeleteColName
+}
+
 func truncateNumerics(
 	items model.Items, targetDWH protos.DBType, unboundedNumericAsString bool,
 	numericTruncator model.CdcTableNumericTruncator,
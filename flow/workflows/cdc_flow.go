@@ -677,14 +677,16 @@ func CDCFlowWorkflow(
 					oldName := mapping.DestinationTableIdentifier
 					newName := strings.TrimSuffix(oldName, "_resync")
 					renameOpts.RenameTableOptions = append(renameOpts.RenameTableOptions, &protos.RenameTableOption{
-						CurrentName: oldName,
-						NewName:     newName,
+						CurrentName:       oldName,
+						NewName:           newName,
+						SoftDeleteColName: mapping.SoftDeleteColName,
 					})
 					mapping.DestinationTableIdentifier = newName
 				} else {
 					renameOpts.RenameTableOptions = append(renameOpts.RenameTableOptions, &protos.RenameTableOption{
-						CurrentName: mapping.DestinationTableIdentifier,
-						NewName:     mapping.DestinationTableIdentifier,
+						CurrentName:       mapping.DestinationTableIdentifier,
+						NewName:           mapping.DestinationTableIdentifier,
+						SoftDeleteColName: mapping.SoftDeleteColName,
 					})
 				}
 			}
@@ -1,6 +1,7 @@
 package peerflow
 
 import (
+	"cmp"
 	"fmt"
 	"log/slog"
 	"slices"
@@ -218,7 +219,7 @@ func (s *SnapshotFlowExecution) cloneTable(
 		MaxParallelWorkers:         numWorkers,
 		StagingPath:                s.config.SnapshotStagingPath,
 		SyncedAtColName:            s.config.SyncedAtColName,
-		SoftDeleteColName:          s.config.SoftDeleteColName,
+		SoftDeleteColName:          cmp.Or(mapping.SoftDeleteColName, s.config.SoftDeleteColName),
 		WriteMode:                  snapshotWriteMode,
 		System:                     s.config.System,
 		Script:                     s.config.Script,
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde_json::Value;
+use sqlparser::ast::{self, Expr};
+
+enum CdcOptionType {
+    String {
+        name: &'static str,
+        default_val: Option<&'static str>,
+        accepted_values: Option<&'static [&'static str]>,
+    },
+    // mirrors created without the option get its default, the same one the UI fills in
+    Int {
+        name: &'static str,
+        default_value: u64,
+        min_value: u64,
+        max_value: u64,
+    },
+    Boolean {
+        name: &'static str,
+        default_value: bool,
+        required: bool,
+    },
+}
+
+impl CdcOptionType {
+    fn name(&self) -> &'static str {
+        match self {
+            CdcOptionType::String { name, .. }
+            | CdcOptionType::Int { name, .. }
+            | CdcOptionType::Boolean { name, .. } => name,
+        }
+    }
+}
+
+const CDC_OPTIONS: &[CdcOptionType] = &[
+    CdcOptionType::Boolean {
+        name: "do_initial_copy",
+        default_value: false,
+        required: true,
+    },
+    CdcOptionType::Boolean {
+        name: "resync",
+        default_value: false,
+        required: false,
+    },
+    CdcOptionType::Boolean {
+        name: "initial_copy_only",
+        default_value: false,
+        required: false,
+    },
+    CdcOptionType::Boolean {
+        name: "disable_peerdb_columns",
+        default_value: false,
+        required: false,
+    },
+    CdcOptionType::String {
+        name: "publication_name",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "replication_slot_name",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::Int {
+        name: "snapshot_num_rows_per_partition",
+        default_value: 250_000,
+        min_value: 1,
+        max_value: 100_000_000,
+    },
+    CdcOptionType::Int {
+        name: "snapshot_max_parallel_workers",
+        default_value: 4,
+        min_value: 1,
+        max_value: 64,
+    },
+    CdcOptionType::Int {
+        name: "snapshot_num_tables_in_parallel",
+        default_value: 1,
+        min_value: 1,
+        max_value: 64,
+    },
+    CdcOptionType::String {
+        name: "snapshot_staging_path",
+        default_val: Some(""),
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "cdc_staging_path",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::Int {
+        name: "max_batch_size",
+        default_value: 250_000,
+        min_value: 1,
+        max_value: 100_000_000,
+    },
+    CdcOptionType::Int {
+        name: "sync_interval",
+        // seconds, at most a day
+        default_value: 60,
+        min_value: 1,
+        max_value: 86_400,
+    },
+    CdcOptionType::String {
+        name: "soft_delete_col_name",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "synced_at_col_name",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "script",
+        default_val: Some(""),
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "system",
+        default_val: Some("Q"),
+        accepted_values: Some(&["Q", "PG"]),
+    },
+    CdcOptionType::String {
+        name: "tags",
+        default_val: None,
+        accepted_values: None,
+    },
+    // written by the parser from the settings after each table mapping
+    CdcOptionType::String {
+        name: "table_settings",
+        default_val: None,
+        accepted_values: None,
+    },
+    CdcOptionType::String {
+        name: "env",
+        default_val: None,
+        accepted_values: None,
+    },
+];
+
+pub fn process_options(
+    mut raw_opts: HashMap<&str, &Expr>,
+) -> anyhow::Result<HashMap<String, Value>> {
+    let mut opts: HashMap<String, Value> = HashMap::new();
+
+    for opt_type in CDC_OPTIONS {
+        match opt_type {
+            CdcOptionType::String {
+                name,
+                default_val,
+                accepted_values,
+            } => {
+                if let Some(raw_value) = raw_opts.remove(*name) {
+                    if let Expr::Value(ast::Value::SingleQuotedString(str)) = raw_value {
+                        if let Some(values) = accepted_values {
+                            if !values.contains(&str.as_str()) {
+                                anyhow::bail!("{} must be one of {:?}", name, values);
+                            }
+                        }
+                        opts.insert(name.to_string(), Value::String(str.clone()));
+                    } else {
+                        anyhow::bail!("Invalid value for {}, expected a string", name);
+                    }
+                } else if let Some(default) = default_val {
+                    opts.insert(name.to_string(), Value::String(default.to_string()));
+                }
+            }
+            CdcOptionType::Int {
+                name,
+                default_value,
+                ..
+            } => {
+                let num = match raw_opts.remove(*name) {
+                    Some(Expr::Value(ast::Value::Number(num_str, _))) => {
+                        int_option_value(name, num_str)?
+                    }
+                    Some(_) => anyhow::bail!("Invalid value for {}, expected a number", name),
+                    None => *default_value,
+                };
+                opts.insert(name.to_string(), Value::Number(num.into()));
+            }
+            CdcOptionType::Boolean {
+                name,
+                default_value,
+                required,
+            } => {
+                if let Some(raw_value) = raw_opts.remove(*name) {
+                    let b = match raw_value {
+                        Expr::Value(ast::Value::Boolean(b)) => *b,
+                        // also support "true" and "false" as strings
+                        Expr::Value(ast::Value::SingleQuotedString(s)) if s == "true" => true,
+                        Expr::Value(ast::Value::SingleQuotedString(s)) if s == "false" => false,
+                        _ => anyhow::bail!("{} must be a boolean", name),
+                    };
+                    opts.insert(name.to_string(), Value::Bool(b));
+                } else if *required {
+                    anyhow::bail!("{} is required", name);
+                } else {
+                    opts.insert(name.to_string(), Value::Bool(*default_value));
+                }
+            }
+        }
+    }
+
+    // all options processed have been removed from the map
+    // so any leftover keys are options that shouldn't be here
+    if let Some(unknown) = raw_opts.into_keys().min() {
+        match closest_option(unknown) {
+            Some(option) => anyhow::bail!(
+                "Unknown option {} for CDC mirrors, did you mean {}?",
+                unknown,
+                option
+            ),
+            None => anyhow::bail!("Unknown option {} for CDC mirrors", unknown),
+        }
+    }
+
+    Ok(opts)
+}
+
+/// Checks a number given for one of the numeric CDC options against the option's range,
+/// ALTER MIRROR takes the same ranges as CREATE MIRROR.
+pub fn int_option_value(name: &str, value: &str) -> anyhow::Result<u64> {
+    let Some((min_value, max_value)) = CDC_OPTIONS.iter().find_map(|opt_type| match opt_type {
+        CdcOptionType::Int {
+            name: option,
+            min_value,
+            max_value,
+            ..
+        } if *option == name => Some((*min_value, *max_value)),
+        _ => None,
+    }) else {
+        anyhow::bail!("{} is not a numeric option", name);
+    };
+    value
+        .parse::<u64>()
+        .ok()
+        .filter(|num| (min_value..=max_value).contains(num))
+        .with_context(|| format!("{name} must be between {min_value} and {max_value}"))
+}
+
+// suggests an option when the unknown one is a few typos away from it
+fn closest_option(unknown: &str) -> Option<&'static str> {
+    CDC_OPTIONS
+        .iter()
+        .map(|opt_type| (edit_distance(unknown, opt_type.name()), opt_type.name()))
+        .filter(|(distance, name)| *distance <= name.len() / 3)
+        .min()
+        .map(|(_, name)| name)
+}
+
+// levenshtein distance, counting inserted, removed and replaced characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = distances[0];
+        distances[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replaced = previous + usize::from(ca != *cb);
+            previous = distances[j + 1];
+            distances[j + 1] = replaced.min(previous + 1).min(distances[j] + 1);
+        }
+    }
+    distances[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(n: &str) -> Expr {
+        Expr::Value(ast::Value::Number(n.to_string(), false))
+    }
+
+    fn string(s: &str) -> Expr {
+        Expr::Value(ast::Value::SingleQuotedString(s.to_string()))
+    }
+
+    fn process(extra: &[(&'static str, Expr)]) -> anyhow::Result<HashMap<String, Value>> {
+        let do_initial_copy = Expr::Value(ast::Value::Boolean(true));
+        let mut raw_opts = HashMap::from([("do_initial_copy", &do_initial_copy)]);
+        raw_opts.extend(extra.iter().map(|(name, value)| (*name, value)));
+        process_options(raw_opts)
+    }
+
+    #[test]
+    fn defaults() {
+        let opts = process(&[]).unwrap();
+        assert_eq!(opts["do_initial_copy"], Value::Bool(true));
+        assert_eq!(opts["resync"], Value::Bool(false));
+        assert_eq!(opts["system"], Value::String("Q".to_string()));
+        assert_eq!(opts["max_batch_size"], Value::from(250_000));
+        assert_eq!(opts["sync_interval"], Value::from(60));
+        assert_eq!(opts["snapshot_max_parallel_workers"], Value::from(4));
+        assert_eq!(opts["snapshot_num_tables_in_parallel"], Value::from(1));
+    }
+
+    #[test]
+    fn do_initial_copy_is_required() {
+        let err = process_options(HashMap::new()).unwrap_err();
+        assert_eq!(err.to_string(), "do_initial_copy is required");
+    }
+
+    #[test]
+    fn unknown_option_suggests_closest() {
+        let err = process(&[("max_batchsize", number("100"))]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown option max_batchsize for CDC mirrors, did you mean max_batch_size?"
+        );
+    }
+
+    #[test]
+    fn unknown_option_without_suggestion() {
+        let err = process(&[("colour", string("blue"))]).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown option colour for CDC mirrors");
+    }
+
+    #[test]
+    fn int_out_of_range() {
+        let err = process(&[("max_batch_size", number("100000001"))]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "max_batch_size must be between 1 and 100000000"
+        );
+
+        let err = process(&[("sync_interval", number("86401"))]).unwrap_err();
+        assert_eq!(err.to_string(), "sync_interval must be between 1 and 86400");
+    }
+
+    #[test]
+    fn zero_is_rejected() {
+        for name in [
+            "max_batch_size",
+            "sync_interval",
+            "snapshot_num_rows_per_partition",
+            "snapshot_max_parallel_workers",
+            "snapshot_num_tables_in_parallel",
+        ] {
+            let err = process(&[(name, number("0"))]).unwrap_err();
+            assert!(err.to_string().starts_with(name), "{err}");
+        }
+    }
+
+    #[test]
+    fn int_in_range() {
+        let opts = process(&[
+            ("max_batch_size", number("1")),
+            ("sync_interval", number("86400")),
+        ])
+        .unwrap();
+        assert_eq!(opts["max_batch_size"], Value::from(1));
+        assert_eq!(opts["sync_interval"], Value::from(86_400));
+    }
+
+    #[test]
+    fn int_option_value_ranges() {
+        assert_eq!(int_option_value("sync_interval", "30").unwrap(), 30);
+        assert!(int_option_value("sync_interval", "0").is_err());
+        assert!(int_option_value("sync_interval", "-1").is_err());
+        assert!(int_option_value("publication_name", "1").is_err());
+    }
+
+    #[test]
+    fn system_accepted_values() {
+        let opts = process(&[("system", string("PG"))]).unwrap();
+        assert_eq!(opts["system"], Value::String("PG".to_string()));
+
+        let err = process(&[("system", string("pg"))]).unwrap_err();
+        assert_eq!(err.to_string(), r#"system must be one of ["Q", "PG"]"#);
+    }
+
+    #[test]
+    fn wrong_value_type() {
+        let err = process(&[("publication_name", number("1"))]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value for publication_name, expected a string"
+        );
+    }
+}
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS nexus_users (
+    name TEXT PRIMARY KEY,
+    salt BYTEA NOT NULL,
+    salted_password BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
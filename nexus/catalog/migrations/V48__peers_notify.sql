@@ -0,0 +1,11 @@
+CREATE OR REPLACE FUNCTION notify_peers_changed() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('peerdb_peers_changed', TG_OP);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS peers_changed ON peers;
+CREATE TRIGGER peers_changed
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON peers
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_peers_changed();
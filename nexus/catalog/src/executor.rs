@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+use deadpool_postgres::Object;
+use peer_cursor::{QueryExecutor, QueryOutput, Schema};
+use peer_postgres::{self, ast};
+use pgwire::{
+    api::Type,
+    error::{PgWireError, PgWireResult},
+};
+use sqlparser::ast::Statement;
+use tokio::sync::{MappedMutexGuard, MutexGuard};
+use tokio_postgres::CancelToken;
+
+use crate::Catalog;
+
+/// Runs the catalog queries of a single session. The first query takes a connection
+/// from the shared pool and the session keeps it until it ends, so that settings, temp
+/// tables and prepared statements stay visible to its next queries.
+pub struct CatalogExecutor {
+    catalog: Arc<Catalog>,
+    pg: tokio::sync::Mutex<Option<Object>>,
+    cancel_token: Mutex<Option<CancelToken>>,
+}
+
+impl CatalogExecutor {
+    pub fn new(catalog: Arc<Catalog>) -> Self {
+        Self {
+            catalog,
+            pg: tokio::sync::Mutex::new(None),
+            cancel_token: Mutex::new(None),
+        }
+    }
+
+    // the connection of the session, a new one replaces it when it died
+    async fn connection(&self) -> PgWireResult<MappedMutexGuard<'_, Object>> {
+        let mut pinned = self.pg.lock().await;
+        let pg = match pinned.take() {
+            Some(pg) if !pg.is_closed() => pg,
+            _ => {
+                let pg = self.catalog.pool.get().await.map_err(|e| {
+                    tracing::error!("unable to get a catalog connection: {}", e);
+                    PgWireError::ApiError(format!("unable to get a catalog connection: {e}").into())
+                })?;
+                if let Ok(mut cancel_token) = self.cancel_token.lock() {
+                    *cancel_token = Some(pg.cancel_token());
+                }
+                pg
+            }
+        };
+        Ok(MutexGuard::map(pinned, |pinned| pinned.insert(pg)))
+    }
+}
+
+#[async_trait::async_trait]
+impl QueryExecutor for CatalogExecutor {
+    async fn execute_raw(&self, query: &str) -> PgWireResult<QueryOutput> {
+        let pg = self.connection().await?;
+        peer_postgres::pg_execute_raw(&pg, query).await
+    }
+
+    #[tracing::instrument(skip(self, stmt), fields(stmt = %stmt))]
+    async fn execute(&self, stmt: &Statement) -> PgWireResult<QueryOutput> {
+        let pg = self.connection().await?;
+        peer_postgres::pg_execute(&pg, ast::PostgresAst { peername: None }, stmt).await
+    }
+
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[value::Value],
+    ) -> PgWireResult<QueryOutput> {
+        let pg = self.connection().await?;
+        peer_postgres::pg_execute_with_params(
+            &pg,
+            ast::PostgresAst { peername: None },
+            stmt,
+            params,
+        )
+        .await
+    }
+
+    async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
+        let pg = self.connection().await?;
+        peer_postgres::pg_describe(&pg, stmt).await
+    }
+
+    async fn describe_params(&self, stmt: &Statement) -> PgWireResult<Option<Vec<Type>>> {
+        let pg = self.connection().await?;
+        peer_postgres::pg_describe_params(&pg, ast::PostgresAst { peername: None }, stmt).await
+    }
+
+    async fn cancel(&self) -> PgWireResult<()> {
+        let cancel_token = self
+            .cancel_token
+            .lock()
+            .ok()
+            .and_then(|cancel_token| cancel_token.clone());
+        let Some(cancel_token) = cancel_token else {
+            return Ok(());
+        };
+        postgres_connection::cancel_postgres(&self.catalog.pg_config, cancel_token)
+            .await
+            .map_err(|e| {
+                tracing::error!("error cancelling query: {}", e);
+                PgWireError::ApiError(format!("error cancelling query: {e}").into())
+            })
+    }
+    // the connection goes back to the pool without what the session left on it, an
+    // unfinished transaction is rolled back as DISCARD ALL cannot run inside one
+    async fn reset(&self) -> PgWireResult<()> {
+        let Some(pg) = self.pg.lock().await.take() else {
+            return Ok(());
+        };
+        if let Ok(mut cancel_token) = self.cancel_token.lock() {
+            *cancel_token = None;
+        }
+        for query in ["ROLLBACK", "DISCARD ALL"] {
+            if let Err(e) = pg.batch_execute(query).await {
+                tracing::error!("error resetting catalog connection: {}", e);
+                // keep the connection out of the pool, it is closed once dropped
+                drop(Object::take(pg));
+                return Err(PgWireError::ApiError(
+                    format!("error resetting catalog connection: {e}").into(),
+                ));
+            }
+        }
+        // the statements prepared by the pool were deallocated along
+        pg.statement_cache.clear();
+        Ok(())
+    }
+}
@@ -1,16 +1,16 @@
 use std::collections::HashMap;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, anyhow};
 use aws_config::{BehaviorVersion, meta::region::RegionProviderChain};
 use aws_sdk_kms::{Client as KmsClient, primitives::Blob};
 use base64::prelude::*;
 use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce, aead::Aead};
-use peer_cursor::{QueryExecutor, QueryOutput, Schema};
-use peer_postgres::{self, ast};
-use pgwire::error::PgWireResult;
-use postgres_connection::{connect_postgres, get_pg_connection_string};
+use deadpool_postgres::{Manager, Object, Pool};
+use futures::StreamExt;
+use postgres_connection::{get_pg_connection_string, make_tls_connector};
 use pt::peerdb_peers::PostgresAuthType;
 use pt::{
     flow_model::QRepFlowJob,
@@ -19,17 +19,34 @@ use pt::{
     prost::Message,
 };
 use serde_json::{self, Value};
-use sqlparser::ast::Statement;
-use tokio_postgres::{Client, types};
+use tokio_postgres::{AsyncMessage, Client, types};
+
+mod executor;
+
+pub use executor::CatalogExecutor;
 
 mod embedded {
     use refinery::embed_migrations;
     embed_migrations!("migrations");
 }
 
+/// Channel notified by the trigger on the peers table.
+const PEERS_CHANGED_CHANNEL: &str = "peerdb_peers_changed";
+
+/// Catalog queries are run on a pool of connections shared by every session,
+/// connections which died are replaced when they are next taken from the pool.
 pub struct Catalog {
-    pg: Client,
+    pool: Pool,
+    pg_config: PostgresConfig,
     kms_key_id: Option<Arc<String>>,
+    peer_cache: Mutex<PeerCache>,
+}
+
+#[derive(Default)]
+struct PeerCache {
+    // bumped by every invalidation, so that peers loaded before it are not cached
+    generation: u64,
+    peers: Option<Arc<HashMap<String, Peer>>>,
 }
 
 pub async fn kms_decrypt(encrypted_payload: &str, kms_key_id: &str) -> anyhow::Result<String> {
@@ -105,20 +122,52 @@ impl CatalogConfig<'_> {
     }
 }
 
+/// A user of the nexus postgres interface, stored with its SCRAM salt and
+/// salted password rather than the plaintext password.
+pub struct NexusUser {
+    pub name: String,
+    pub salt: Vec<u8>,
+    pub salted_password: Vec<u8>,
+}
+
 impl Catalog {
     pub async fn new(
         pt_config: pt::peerdb_peers::PostgresConfig,
         kms_key_id: &Option<Arc<String>>,
+        pool_size: usize,
     ) -> anyhow::Result<Self> {
-        let (pg, _) = connect_postgres(&pt_config).await?;
+        let tokio_postgres_config = get_pg_connection_string(&pt_config)
+            .parse::<tokio_postgres::Config>()
+            .context("Failed to create tokio_postgres::Config from catalog config")?;
+        let manager = Manager::new(tokio_postgres_config, make_tls_connector()?);
+        let pool = Pool::builder(manager)
+            .max_size(pool_size)
+            .build()
+            .context("Failed to create catalog connection pool")?;
+
+        // fail early if the catalog is unreachable
+        pool.get()
+            .await
+            .context("error encountered while connecting to catalog")?;
+
         Ok(Self {
-            pg,
+            pool,
+            pg_config: pt_config,
             kms_key_id: kms_key_id.clone(),
+            peer_cache: Mutex::new(PeerCache::default()),
         })
     }
 
-    pub async fn run_migrations(&mut self) -> anyhow::Result<()> {
-        run_migrations(&mut self.pg).await
+    async fn get_connection(&self) -> anyhow::Result<Object> {
+        self.pool
+            .get()
+            .await
+            .context("unable to get a catalog connection")
+    }
+
+    pub async fn run_migrations(&self) -> anyhow::Result<()> {
+        let mut pg = self.get_connection().await?;
+        run_migrations(&mut pg).await
     }
 
     async fn env_enc_key(&self, enc_key_id: &str) -> anyhow::Result<Vec<u8>> {
@@ -177,31 +226,30 @@ impl Catalog {
 
     // get peer id as i32
     pub async fn get_peer_id_i32(&self, peer_name: &str) -> anyhow::Result<i32> {
-        let stmt = self
-            .pg
+        let pg = self.get_connection().await?;
+        let stmt = pg
             .prepare_typed(
                 "SELECT id FROM public.peers WHERE name = $1",
                 &[types::Type::TEXT],
             )
             .await?;
 
-        self.pg
-            .query_opt(&stmt, &[&peer_name])
+        pg.query_opt(&stmt, &[&peer_name])
             .await?
             .map(|row| row.get(0))
             .context("Failed to get peer id")
     }
 
     pub async fn get_peers(&self) -> anyhow::Result<HashMap<String, Peer>> {
-        let stmt = self
-            .pg
+        let pg = self.get_connection().await?;
+        let stmt = pg
             .prepare_typed(
                 "SELECT name, type, options, enc_key_id FROM public.peers",
                 &[],
             )
             .await?;
 
-        let rows = self.pg.query(&stmt, &[]).await?;
+        let rows = pg.query(&stmt, &[]).await?;
 
         let mut peers = HashMap::with_capacity(rows.len());
 
@@ -224,16 +272,82 @@ impl Catalog {
         Ok(peers)
     }
 
+    /// Same as [`Self::get_peers`], but the peers are only loaded and decrypted again
+    /// after they were invalidated by [`Self::invalidate_peers`].
+    pub async fn get_cached_peers(&self) -> anyhow::Result<Arc<HashMap<String, Peer>>> {
+        let generation = {
+            let cache = self.peer_cache.lock().unwrap();
+            if let Some(peers) = &cache.peers {
+                return Ok(peers.clone());
+            }
+            cache.generation
+        };
+
+        let peers = Arc::new(self.get_peers().await?);
+        let mut cache = self.peer_cache.lock().unwrap();
+        if cache.generation == generation {
+            cache.peers = Some(peers.clone());
+        }
+        Ok(peers)
+    }
+
+    pub fn invalidate_peers(&self) {
+        let mut cache = self.peer_cache.lock().unwrap();
+        cache.generation += 1;
+        cache.peers = None;
+    }
+
+    /// Invalidate the cached peers whenever the peers table changes, including changes
+    /// made by the flow api or other nexus instances. Runs until the process exits.
+    pub async fn listen_for_peer_changes(self: Arc<Self>) {
+        loop {
+            if let Err(err) = self.listen_peers_changed().await {
+                tracing::warn!(
+                    "listening for peer changes failed, retrying in 5 seconds: {:?}",
+                    err
+                );
+            }
+            // notifications are lost while there is no listener
+            self.invalidate_peers();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
+
+    async fn listen_peers_changed(self: &Arc<Self>) -> anyhow::Result<()> {
+        let conn_str = get_pg_connection_string(&self.pg_config);
+        let (client, mut connection) =
+            tokio_postgres::connect(&conn_str, make_tls_connector()?).await?;
+
+        let catalog = self.clone();
+        let notifications = tokio::spawn(async move {
+            let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+            while let Some(message) = messages.next().await {
+                if let AsyncMessage::Notification(_) = message? {
+                    catalog.invalidate_peers();
+                }
+            }
+            Ok::<_, tokio_postgres::Error>(())
+        });
+
+        client
+            .batch_execute(&format!("LISTEN {PEERS_CHANGED_CHANNEL}"))
+            .await?;
+        // anything may have changed before the LISTEN
+        self.invalidate_peers();
+        notifications.await??;
+        Ok(())
+    }
+
     pub async fn get_peer(&self, peer_name: &str) -> anyhow::Result<Peer> {
-        let stmt = self
-            .pg
+        let pg = self.get_connection().await?;
+        let stmt = pg
             .prepare_typed(
                 "SELECT name, type, options, enc_key_id FROM public.peers WHERE name = $1",
                 &[],
             )
             .await?;
 
-        let rows = self.pg.query(&stmt, &[&peer_name]).await?;
+        let rows = pg.query(&stmt, &[&peer_name]).await?;
 
         if let Some(row) = rows.first() {
             let name: &str = row.get(0);
@@ -345,15 +459,15 @@ impl Catalog {
         &self,
         job_name: &str,
     ) -> anyhow::Result<Option<QRepFlowJob>> {
-        let stmt = self
-            .pg
-            .prepare_typed("SELECT f.*, sp.name as source_peer_name, dp.name as destination_peer_name FROM public.flows as f
+        let pg = self.get_connection().await?;
+        let stmt = pg
+                        .prepare_typed("SELECT f.*, sp.name as source_peer_name, dp.name as destination_peer_name FROM public.flows as f
                             INNER JOIN public.peers as sp ON f.source_peer = sp.id
                             INNER JOIN public.peers as dp ON f.destination_peer = dp.id
                             WHERE f.name = $1 AND f.query_string IS NOT NULL", &[types::Type::TEXT])
             .await?;
 
-        let job = self.pg.query_opt(&stmt, &[&job_name]).await?.map(|row| {
+        let job = pg.query_opt(&stmt, &[&job_name]).await?.map(|row| {
             let flow_opts: HashMap<String, Value> = row
                 .get::<&str, Option<Value>>("flow_metadata")
                 .and_then(|flow_opts| serde_json::from_value(flow_opts).ok())
@@ -368,6 +482,10 @@ impl Catalog {
                 flow_options: flow_opts,
                 // we set the disabled flag to false by default
                 disabled: false,
+                tags: row
+                    .get::<&str, Option<Value>>("tags")
+                    .and_then(|tags| serde_json::from_value(tags).ok())
+                    .unwrap_or_default(),
             }
         });
 
@@ -384,13 +502,13 @@ impl Catalog {
             .await
             .context("unable to get destination peer id")?;
 
-        let stmt = self
-            .pg
-            .prepare_typed(
+        let pg = self.get_connection().await?;
+        let stmt = pg
+                        .prepare_typed(
                 "INSERT INTO flows (name, source_peer, destination_peer, description,
-                     destination_table_identifier, query_string, flow_metadata) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                     destination_table_identifier, query_string, flow_metadata, tags) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
                 &[types::Type::TEXT, types::Type::INT4, types::Type::INT4, types::Type::TEXT,
-                 types::Type::TEXT, types::Type::TEXT, types::Type::JSONB],
+                 types::Type::TEXT, types::Type::TEXT, types::Type::JSONB, types::Type::JSONB],
             )
             .await?;
 
@@ -398,8 +516,7 @@ impl Catalog {
             return Err(anyhow!("destination_table_name not found in flow options"));
         };
 
-        let _rows = self
-            .pg
+        let _rows = pg
             .execute(
                 &stmt,
                 &[
@@ -411,6 +528,7 @@ impl Catalog {
                     &job.query_string,
                     &serde_json::to_value(job.flow_options.clone())
                         .context("unable to serialize flow options")?,
+                    &serde_json::to_value(&job.tags).context("unable to serialize tags")?,
                 ],
             )
             .await?;
@@ -423,8 +541,8 @@ impl Catalog {
         flow_job_name: &str,
         workflow_id: &str,
     ) -> anyhow::Result<()> {
-        let rows = self
-            .pg
+        let pg = self.get_connection().await?;
+        let rows = pg
             .execute(
                 "UPDATE FLOWS SET WORKFLOW_ID = $1 WHERE NAME = $2",
                 &[&workflow_id, &flow_job_name],
@@ -436,9 +554,48 @@ impl Catalog {
         Ok(())
     }
 
+    /// The config of a CDC mirror as last synced by the flow workflow, None for unknown mirrors.
+    pub async fn get_cdc_flow_config(
+        &self,
+        flow_job_name: &str,
+    ) -> anyhow::Result<Option<pt::peerdb_flow::FlowConnectionConfigs>> {
+        let pg = self.get_connection().await?;
+        let row = pg
+            .query_opt(
+                "SELECT config_proto FROM flows WHERE name = $1 AND query_string IS NULL",
+                &[&flow_job_name],
+            )
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let config_proto: Option<&[u8]> = row.get(0);
+        let config_proto =
+            config_proto.with_context(|| format!("mirror {flow_job_name} has no config"))?;
+        let config = pt::peerdb_flow::FlowConnectionConfigs::decode(config_proto)
+            .with_context(|| format!("unable to decode config of mirror {flow_job_name}"))?;
+        Ok(Some(config))
+    }
+
+    /// Tags of all mirrors by mirror name, mirrors without tags are left out.
+    pub async fn get_flow_tags(&self) -> anyhow::Result<HashMap<String, HashMap<String, String>>> {
+        let pg = self.get_connection().await?;
+        let rows = pg
+            .query("SELECT name, tags FROM flows WHERE tags IS NOT NULL", &[])
+            .await?;
+        let mut flow_tags = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let name: String = row.get("name");
+            let tags = serde_json::from_value(row.get("tags"))
+                .with_context(|| format!("unable to decode tags of mirror {name}"))?;
+            flow_tags.insert(name, tags);
+        }
+        Ok(flow_tags)
+    }
+
     pub async fn flow_name_exists(&self, flow_job_name: &str) -> anyhow::Result<bool> {
-        let row = self
-            .pg
+        let pg = self.get_connection().await?;
+        let row = pg
             .query_one(
                 "SELECT EXISTS(SELECT * FROM flows WHERE name = $1)",
                 &[&flow_job_name],
@@ -450,8 +607,8 @@ impl Catalog {
     }
 
     pub async fn check_peer_entry(&self, peer_name: &str) -> anyhow::Result<i64> {
-        let peer_check = self
-            .pg
+        let pg = self.get_connection().await?;
+        let peer_check = pg
             .query_one(
                 "SELECT COUNT(*) FROM public.peers WHERE name = $1",
                 &[&peer_name],
@@ -460,20 +617,55 @@ impl Catalog {
         let peer_count: i64 = peer_check.get(0);
         Ok(peer_count)
     }
-}
 
-#[async_trait::async_trait]
-impl QueryExecutor for Catalog {
-    async fn execute_raw(&self, query: &str) -> PgWireResult<QueryOutput> {
-        peer_postgres::pg_execute_raw(&self.pg, query).await
+    pub async fn get_user(&self, user_name: &str) -> anyhow::Result<Option<NexusUser>> {
+        let pg = self.get_connection().await?;
+        let row = pg
+            .query_opt(
+                "SELECT name, salt, salted_password FROM nexus_users WHERE name = $1",
+                &[&user_name],
+            )
+            .await?;
+
+        Ok(row.map(|row| NexusUser {
+            name: row.get(0),
+            salt: row.get(1),
+            salted_password: row.get(2),
+        }))
     }
 
-    #[tracing::instrument(skip(self, stmt), fields(stmt = %stmt))]
-    async fn execute(&self, stmt: &Statement) -> PgWireResult<QueryOutput> {
-        peer_postgres::pg_execute(&self.pg, ast::PostgresAst { peername: None }, stmt).await
+    // returns false if a user with the same name already exists
+    pub async fn create_user(&self, user: &NexusUser) -> anyhow::Result<bool> {
+        let pg = self.get_connection().await?;
+        let rows = pg
+            .execute(
+                "INSERT INTO nexus_users (name, salt, salted_password) VALUES ($1, $2, $3)
+                 ON CONFLICT (name) DO NOTHING",
+                &[&user.name, &user.salt, &user.salted_password],
+            )
+            .await?;
+        Ok(rows != 0)
     }
 
-    async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
-        peer_postgres::pg_describe(&self.pg, stmt).await
+    // returns false if no such user exists
+    pub async fn update_user_password(&self, user: &NexusUser) -> anyhow::Result<bool> {
+        let pg = self.get_connection().await?;
+        let rows = pg
+            .execute(
+                "UPDATE nexus_users SET salt = $2, salted_password = $3, updated_at = now()
+                 WHERE name = $1",
+                &[&user.name, &user.salt, &user.salted_password],
+            )
+            .await?;
+        Ok(rows != 0)
+    }
+
+    // returns false if no such user exists
+    pub async fn drop_user(&self, user_name: &str) -> anyhow::Result<bool> {
+        let pg = self.get_connection().await?;
+        let rows = pg
+            .execute("DELETE FROM nexus_users WHERE name = $1", &[&user_name])
+            .await?;
+        Ok(rows != 0)
     }
 }
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
 use pt::{
-    flow_model::{FlowJob, QRepFlowJob},
-    peerdb_flow::{QRepWriteMode, QRepWriteType, TypeSystem},
+    flow_model::{FlowJob, FlowJobTableMapping, QRepFlowJob},
+    peerdb_flow::{QRepWriteMode, QRepWriteType, TableEngine, TypeSystem},
     peerdb_route, tonic,
 };
 use serde_json::Value;
@@ -97,61 +100,7 @@ impl FlowGrpcClient {
         src: String,
         dst: String,
     ) -> anyhow::Result<String> {
-        let table_mappings: Vec<pt::peerdb_flow::TableMapping> = job
-            .table_mappings
-            .iter()
-            .map(|mapping| pt::peerdb_flow::TableMapping {
-                source_table_identifier: mapping.source_table_identifier.clone(),
-                destination_table_identifier: mapping.destination_table_identifier.clone(),
-                partition_key: mapping.partition_key.clone().unwrap_or_default(),
-                exclude: mapping.exclude.clone(),
-                ..Default::default()
-            })
-            .collect::<Vec<_>>();
-
-        let do_initial_snapshot = job.do_initial_copy;
-        let publication_name = job.publication_name.clone();
-        let replication_slot_name = job.replication_slot_name.clone();
-        let snapshot_num_rows_per_partition = job.snapshot_num_rows_per_partition;
-        let snapshot_max_parallel_workers = job.snapshot_max_parallel_workers;
-        let snapshot_num_tables_in_parallel = job.snapshot_num_tables_in_parallel;
-        let Some(system) = TypeSystem::from_str_name(&job.system) else {
-            return anyhow::Result::Err(anyhow::anyhow!("invalid system {}", job.system));
-        };
-
-        let mut flow_conn_cfg = pt::peerdb_flow::FlowConnectionConfigs {
-            source_name: src,
-            destination_name: dst,
-            flow_job_name: job.name.clone(),
-            table_mappings,
-            do_initial_snapshot,
-            publication_name: publication_name.unwrap_or_default(),
-            snapshot_num_rows_per_partition: snapshot_num_rows_per_partition.unwrap_or(0),
-            snapshot_max_parallel_workers: snapshot_max_parallel_workers.unwrap_or(0),
-            snapshot_num_tables_in_parallel: snapshot_num_tables_in_parallel.unwrap_or(0),
-            snapshot_staging_path: job.snapshot_staging_path.clone(),
-            cdc_staging_path: job.cdc_staging_path.clone().unwrap_or_default(),
-            replication_slot_name: replication_slot_name.unwrap_or_default(),
-            max_batch_size: job.max_batch_size.unwrap_or_default(),
-            resync: job.resync,
-            soft_delete_col_name: job.soft_delete_col_name.clone().unwrap_or_default(),
-            synced_at_col_name: job
-                .synced_at_col_name
-                .clone()
-                .unwrap_or("_PEERDB_SYNCED_AT".to_string()),
-            initial_snapshot_only: job.initial_snapshot_only,
-            script: job.script.clone(),
-            system: system as i32,
-            idle_timeout_seconds: job.sync_interval.unwrap_or_default(),
-            env: Default::default(),
-            version: 0, // filled in by server
-        };
-
-        if job.disable_peerdb_columns {
-            flow_conn_cfg.soft_delete_col_name = "".to_string();
-            flow_conn_cfg.synced_at_col_name = "".to_string();
-        }
-
+        let flow_conn_cfg = cdc_flow_config(job, src, dst)?;
         self.start_peer_flow(flow_conn_cfg).await
     }
 
@@ -161,97 +110,7 @@ impl FlowGrpcClient {
         src: String,
         dst: String,
     ) -> anyhow::Result<String> {
-        let mut cfg = pt::peerdb_flow::QRepConfig {
-            source_name: src,
-            destination_name: dst,
-            flow_job_name: job.name.clone(),
-            query: job.query_string.clone(),
-            ..Default::default()
-        };
-
-        for (key, value) in &job.flow_options {
-            match value {
-                Value::String(s) => match key.as_str() {
-                    "destination_table_name" => cfg.destination_table_identifier.clone_from(s),
-                    "watermark_column" => cfg.watermark_column.clone_from(s),
-                    "watermark_table_name" => cfg.watermark_table.clone_from(s),
-                    "mode" => {
-                        let mut wm = QRepWriteMode {
-                            write_type: QRepWriteType::QrepWriteModeAppend as i32,
-                            upsert_key_columns: vec![],
-                        };
-                        match s.as_str() {
-                            "upsert" => {
-                                wm.write_type = QRepWriteType::QrepWriteModeUpsert as i32;
-                                // get the unique key columns from the options
-                                let unique_key_columns = job.flow_options.get("unique_key_columns");
-                                if let Some(Value::Array(arr)) = unique_key_columns {
-                                    for v in arr {
-                                        if let Value::String(s) = v {
-                                            wm.upsert_key_columns.push(s.clone());
-                                        }
-                                    }
-                                }
-                                cfg.write_mode = Some(wm);
-                            }
-                            "append" => cfg.write_mode = Some(wm),
-                            "overwrite" => {
-                                wm.write_type = QRepWriteType::QrepWriteModeOverwrite as i32;
-                                cfg.write_mode = Some(wm);
-                            }
-                            _ => return anyhow::Result::Err(anyhow::anyhow!("invalid mode {}", s)),
-                        }
-                    }
-                    "staging_path" => cfg.staging_path.clone_from(s),
-                    _ => return anyhow::Result::Err(anyhow::anyhow!("invalid str option {}", key)),
-                },
-                Value::Number(n) => match key.as_str() {
-                    "parallelism" => {
-                        if let Some(n) = n.as_i64() {
-                            cfg.max_parallel_workers = n as u32;
-                        }
-                    }
-                    "refresh_interval" => {
-                        if let Some(n) = n.as_i64() {
-                            cfg.wait_between_batches_seconds = n as u32;
-                        }
-                    }
-                    "num_rows_per_partition" => {
-                        if let Some(n) = n.as_i64() {
-                            cfg.num_rows_per_partition = n as u32;
-                        }
-                    }
-                    _ => return anyhow::Result::Err(anyhow::anyhow!("invalid num option {}", key)),
-                },
-                Value::Bool(v) => {
-                    if key == "initial_copy_only" {
-                        cfg.initial_copy_only = *v;
-                    } else if key == "setup_watermark_table_on_destination" {
-                        cfg.setup_watermark_table_on_destination = *v;
-                    } else if key == "dst_table_full_resync" {
-                        cfg.dst_table_full_resync = *v;
-                    } else {
-                        return anyhow::Result::Err(anyhow::anyhow!("invalid bool option {}", key));
-                    }
-                }
-                _ => {
-                    tracing::info!("ignoring option {} with value {:?}", key, value);
-                }
-            }
-        }
-        if !cfg.initial_copy_only {
-            if let Some(QRepWriteMode {
-                write_type: wt,
-                upsert_key_columns: _,
-            }) = cfg.write_mode
-            {
-                if wt == QRepWriteType::QrepWriteModeOverwrite as i32 {
-                    return anyhow::Result::Err(anyhow::anyhow!(
-                        "write mode overwrite can only be set with initial_copy_only = true"
-                    ));
-                }
-            }
-        }
+        let cfg = qrep_config(job, src, dst)?;
         self.start_query_replication_flow(&cfg).await
     }
 
@@ -288,6 +147,233 @@ impl FlowGrpcClient {
         }
     }
 
+    /// Checks a peer definition without creating it, the message says why it is invalid.
+    pub async fn validate_peer(
+        &mut self,
+        peer: &pt::peerdb_peers::Peer,
+    ) -> anyhow::Result<Option<String>> {
+        let validate_request = peerdb_route::ValidatePeerRequest {
+            peer: Some(peer.clone()),
+        };
+        let response = self
+            .client
+            .validate_peer(validate_request)
+            .await?
+            .into_inner();
+        if response.status == peerdb_route::ValidatePeerStatus::Valid as i32 {
+            Ok(None)
+        } else {
+            Ok(Some(response.message))
+        }
+    }
+
+    /// Checks a CDC mirror config without creating it, the message says why it is invalid.
+    pub async fn validate_cdc_mirror(
+        &mut self,
+        connection_configs: pt::peerdb_flow::FlowConnectionConfigs,
+    ) -> anyhow::Result<Option<String>> {
+        let validate_request = peerdb_route::CreateCdcFlowRequest {
+            connection_configs: Some(connection_configs),
+        };
+        match self.client.validate_cdc_mirror(validate_request).await {
+            Ok(_) => Ok(None),
+            // the flow service could not be reached, this says nothing about the mirror
+            Err(status) if status.code() == tonic::Code::Unavailable => Err(status.into()),
+            Err(status) => Ok(Some(status.message().to_owned())),
+        }
+    }
+
+    pub async fn list_peers(&mut self) -> anyhow::Result<Vec<peerdb_route::PeerListItem>> {
+        let response = self
+            .client
+            .list_peers(peerdb_route::ListPeersRequest {})
+            .await?;
+        Ok(response.into_inner().items)
+    }
+
+    pub async fn list_mirrors(&mut self) -> anyhow::Result<Vec<peerdb_route::ListMirrorsItem>> {
+        let response = self
+            .client
+            .list_mirrors(peerdb_route::ListMirrorsRequest {})
+            .await?;
+        Ok(response.into_inner().mirrors)
+    }
+
+    pub async fn mirror_status(
+        &mut self,
+        flow_job_name: &str,
+    ) -> anyhow::Result<peerdb_route::MirrorStatusResponse> {
+        let mirror_status_req = peerdb_route::MirrorStatusRequest {
+            flow_job_name: flow_job_name.to_owned(),
+            include_flow_info: false,
+            exclude_batches: true,
+        };
+        let response = self.client.mirror_status(mirror_status_req).await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn initial_load_summary(
+        &mut self,
+        flow_job_name: &str,
+    ) -> anyhow::Result<Vec<peerdb_route::CloneTableSummary>> {
+        let summary_req = peerdb_route::InitialLoadSummaryRequest {
+            parent_mirror_name: flow_job_name.to_owned(),
+        };
+        let response = self.client.initial_load_summary(summary_req).await?;
+        Ok(response.into_inner().table_summaries)
+    }
+
+    // the latest batches come first
+    pub async fn cdc_batches(
+        &mut self,
+        flow_job_name: &str,
+        limit: u32,
+    ) -> anyhow::Result<Vec<peerdb_route::CdcBatch>> {
+        let batches_req = peerdb_route::GetCdcBatchesRequest {
+            flow_job_name: flow_job_name.to_owned(),
+            limit,
+            ascending: false,
+            before_id: 0,
+            after_id: 0,
+        };
+        let response = self.client.get_cdc_batches(batches_req).await?;
+        Ok(response.into_inner().cdc_batches)
+    }
+
+    pub async fn last_mirror_error(
+        &mut self,
+        flow_job_name: &str,
+    ) -> anyhow::Result<Option<peerdb_route::MirrorLog>> {
+        let logs_req = peerdb_route::ListMirrorLogsRequest {
+            flow_job_name: flow_job_name.to_owned(),
+            level: "error".to_owned(),
+            page: 0,
+            num_per_page: 1,
+            before_id: 0,
+            after_id: 0,
+        };
+        let response = self.client.list_mirror_logs(logs_req).await?;
+        Ok(response.into_inner().errors.into_iter().next())
+    }
+
+    /// Replaces all tags of the mirror.
+    pub async fn create_or_replace_flow_tags(
+        &mut self,
+        flow_name: &str,
+        tags: &HashMap<String, String>,
+    ) -> anyhow::Result<()> {
+        let tags_req = peerdb_route::CreateOrReplaceFlowTagsRequest {
+            flow_name: flow_name.to_owned(),
+            tags: tags
+                .iter()
+                .map(|(key, value)| peerdb_route::FlowTag {
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+                .collect(),
+        };
+        self.client.create_or_replace_flow_tags(tags_req).await?;
+        Ok(())
+    }
+
+    pub async fn get_flow_tags(
+        &mut self,
+        flow_name: &str,
+    ) -> anyhow::Result<HashMap<String, String>> {
+        let tags_req = peerdb_route::GetFlowTagsRequest {
+            flow_name: flow_name.to_owned(),
+        };
+        let response = self.client.get_flow_tags(tags_req).await?;
+        Ok(response
+            .into_inner()
+            .tags
+            .into_iter()
+            .map(|tag| (tag.key, tag.value))
+            .collect())
+    }
+
+    pub async fn alert_configs(&mut self) -> anyhow::Result<Vec<peerdb_route::AlertConfig>> {
+        let alerts_req = peerdb_route::GetAlertConfigsRequest {};
+        let response = self.client.get_alert_configs(alerts_req).await?;
+        Ok(response.into_inner().configs)
+    }
+
+    /// Creates the alert config when its id is -1, otherwise replaces it. Returns its id.
+    pub async fn post_alert_config(
+        &mut self,
+        config: peerdb_route::AlertConfig,
+    ) -> anyhow::Result<i32> {
+        let alert_req = peerdb_route::PostAlertConfigRequest {
+            config: Some(config),
+        };
+        let response = self.client.post_alert_config(alert_req).await?;
+        Ok(response.into_inner().id)
+    }
+
+    pub async fn delete_alert_config(&mut self, id: i32) -> anyhow::Result<()> {
+        let alert_req = peerdb_route::DeleteAlertConfigRequest { id };
+        self.client.delete_alert_config(alert_req).await?;
+        Ok(())
+    }
+
+    pub async fn dynamic_settings(&mut self) -> anyhow::Result<Vec<peerdb_route::DynamicSetting>> {
+        let settings_req = peerdb_route::GetDynamicSettingsRequest {};
+        let response = self.client.get_dynamic_settings(settings_req).await?;
+        Ok(response.into_inner().settings)
+    }
+
+    pub async fn post_dynamic_setting(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        let setting_req = peerdb_route::PostDynamicSettingRequest {
+            name: name.to_owned(),
+            value: Some(value.to_owned()),
+        };
+        self.client.post_dynamic_setting(setting_req).await?;
+        Ok(())
+    }
+
+    pub async fn scripts(&mut self) -> anyhow::Result<Vec<peerdb_route::Script>> {
+        // an id of -1 lists all scripts
+        let scripts_req = peerdb_route::GetScriptsRequest { id: -1 };
+        let response = self.client.get_scripts(scripts_req).await?;
+        Ok(response.into_inner().scripts)
+    }
+
+    /// Creates the script when its id is -1, otherwise replaces it. Returns its id.
+    pub async fn post_script(&mut self, script: peerdb_route::Script) -> anyhow::Result<i32> {
+        let script_req = peerdb_route::PostScriptRequest {
+            script: Some(script),
+        };
+        let response = self.client.post_script(script_req).await?;
+        Ok(response.into_inner().id)
+    }
+
+    pub async fn delete_script(&mut self, id: i32) -> anyhow::Result<()> {
+        let script_req = peerdb_route::DeleteScriptRequest { id };
+        self.client.delete_script(script_req).await?;
+        Ok(())
+    }
+
+    /// Starts the workflow which enters or exits maintenance, returns its workflow id.
+    pub async fn maintenance(
+        &mut self,
+        status: peerdb_route::MaintenanceStatus,
+    ) -> anyhow::Result<String> {
+        let maintenance_req = peerdb_route::MaintenanceRequest {
+            status: status.into(),
+            use_peerflow_task_queue: false,
+        };
+        let response = self.client.maintenance(maintenance_req).await?;
+        Ok(response.into_inner().workflow_id)
+    }
+
+    pub async fn maintenance_status(
+        &mut self,
+    ) -> anyhow::Result<peerdb_route::MaintenanceStatusResponse> {
+        let status_req = peerdb_route::MaintenanceStatusRequest {};
+        let response = self.client.get_maintenance_status(status_req).await?;
+        Ok(response.into_inner())
+    }
+
     pub async fn resync_mirror(&mut self, flow_job_name: &str) -> anyhow::Result<()> {
         let state_change_req = pt::peerdb_route::FlowStateChangeRequest {
             flow_job_name: flow_job_name.to_owned(),
@@ -300,3 +386,346 @@ impl FlowGrpcClient {
         Ok(())
     }
 }
+
+/// ClickHouse table engines are named without their CH_ENGINE_ prefix, like merge_tree.
+pub fn table_engine(name: &str) -> anyhow::Result<TableEngine> {
+    TableEngine::from_str_name(&format!("CH_ENGINE_{}", name.to_uppercase()))
+        .with_context(|| format!("unknown table engine {name}"))
+}
+
+/// The mapping CREATE MIRROR and ALTER MIRROR ADD TABLE submit for a table.
+pub fn table_mapping(
+    mapping: &FlowJobTableMapping,
+) -> anyhow::Result<pt::peerdb_flow::TableMapping> {
+    let settings = &mapping.settings;
+    let columns = settings
+        .columns
+        .iter()
+        .map(|column| pt::peerdb_flow::ColumnSetting {
+            source_name: column.source_name.clone(),
+            destination_name: column.destination_name.clone().unwrap_or_default(),
+            destination_type: column.destination_type.clone().unwrap_or_default(),
+            ordering: column.ordering,
+            nullable_enabled: column.nullable,
+        })
+        .collect();
+    let engine = match &settings.engine {
+        Some(engine) => table_engine(engine)?,
+        None => TableEngine::default(),
+    };
+    Ok(pt::peerdb_flow::TableMapping {
+        source_table_identifier: mapping.source_table_identifier.clone(),
+        destination_table_identifier: mapping.destination_table_identifier.clone(),
+        partition_key: mapping.partition_key.clone().unwrap_or_default(),
+        exclude: mapping.exclude.clone(),
+        columns,
+        engine: engine.into(),
+        sharding_key: settings.sharding_key.clone().unwrap_or_default(),
+        policy_name: settings.policy_name.clone().unwrap_or_default(),
+        soft_delete_col_name: settings.soft_delete_col_name.clone().unwrap_or_default(),
+    })
+}
+
+/// The config CREATE MIRROR submits for a CDC mirror.
+pub fn cdc_flow_config(
+    job: &FlowJob,
+    src: String,
+    dst: String,
+) -> anyhow::Result<pt::peerdb_flow::FlowConnectionConfigs> {
+    let table_mappings = job
+        .table_mappings
+        .iter()
+        .map(table_mapping)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let do_initial_snapshot = job.do_initial_copy;
+    let publication_name = job.publication_name.clone();
+    let replication_slot_name = job.replication_slot_name.clone();
+    let snapshot_num_rows_per_partition = job.snapshot_num_rows_per_partition;
+    let snapshot_max_parallel_workers = job.snapshot_max_parallel_workers;
+    let snapshot_num_tables_in_parallel = job.snapshot_num_tables_in_parallel;
+    let Some(system) = TypeSystem::from_str_name(&job.system) else {
+        return anyhow::Result::Err(anyhow::anyhow!("invalid system {}", job.system));
+    };
+
+    let mut flow_conn_cfg = pt::peerdb_flow::FlowConnectionConfigs {
+        source_name: src,
+        destination_name: dst,
+        flow_job_name: job.name.clone(),
+        table_mappings,
+        do_initial_snapshot,
+        publication_name: publication_name.unwrap_or_default(),
+        snapshot_num_rows_per_partition: snapshot_num_rows_per_partition.unwrap_or(0),
+        snapshot_max_parallel_workers: snapshot_max_parallel_workers.unwrap_or(0),
+        snapshot_num_tables_in_parallel: snapshot_num_tables_in_parallel.unwrap_or(0),
+        snapshot_staging_path: job.snapshot_staging_path.clone(),
+        cdc_staging_path: job.cdc_staging_path.clone().unwrap_or_default(),
+        replication_slot_name: replication_slot_name.unwrap_or_default(),
+        max_batch_size: job.max_batch_size.unwrap_or_default(),
+        resync: job.resync,
+        soft_delete_col_name: job.soft_delete_col_name.clone().unwrap_or_default(),
+        synced_at_col_name: job
+            .synced_at_col_name
+            .clone()
+            .unwrap_or("_PEERDB_SYNCED_AT".to_string()),
+        initial_snapshot_only: job.initial_snapshot_only,
+        script: job.script.clone(),
+        system: system as i32,
+        idle_timeout_seconds: job.sync_interval.unwrap_or_default(),
+        env: job.env.clone(),
+        version: 0, // filled in by server
+    };
+
+    if job.disable_peerdb_columns {
+        flow_conn_cfg.soft_delete_col_name = "".to_string();
+        flow_conn_cfg.synced_at_col_name = "".to_string();
+    }
+
+    Ok(flow_conn_cfg)
+}
+
+/// The config CREATE MIRROR submits for a query replication mirror.
+pub fn qrep_config(
+    job: &QRepFlowJob,
+    src: String,
+    dst: String,
+) -> anyhow::Result<pt::peerdb_flow::QRepConfig> {
+    let mut cfg = pt::peerdb_flow::QRepConfig {
+        source_name: src,
+        destination_name: dst,
+        flow_job_name: job.name.clone(),
+        query: job.query_string.clone(),
+        ..Default::default()
+    };
+
+    for (key, value) in &job.flow_options {
+        match value {
+            Value::String(s) => match key.as_str() {
+                "destination_table_name" => cfg.destination_table_identifier.clone_from(s),
+                "watermark_column" => cfg.watermark_column.clone_from(s),
+                "watermark_table_name" => cfg.watermark_table.clone_from(s),
+                "mode" => {
+                    let mut wm = QRepWriteMode {
+                        write_type: QRepWriteType::QrepWriteModeAppend as i32,
+                        upsert_key_columns: vec![],
+                    };
+                    match s.as_str() {
+                        "upsert" => {
+                            wm.write_type = QRepWriteType::QrepWriteModeUpsert as i32;
+                            // get the unique key columns from the options
+                            let unique_key_columns = job.flow_options.get("unique_key_columns");
+                            if let Some(Value::Array(arr)) = unique_key_columns {
+                                for v in arr {
+                                    if let Value::String(s) = v {
+                                        wm.upsert_key_columns.push(s.clone());
+                                    }
+                                }
+                            }
+                            cfg.write_mode = Some(wm);
+                        }
+                        "append" => cfg.write_mode = Some(wm),
+                        "overwrite" => {
+                            wm.write_type = QRepWriteType::QrepWriteModeOverwrite as i32;
+                            cfg.write_mode = Some(wm);
+                        }
+                        _ => return anyhow::Result::Err(anyhow::anyhow!("invalid mode {}", s)),
+                    }
+                }
+                "staging_path" => cfg.staging_path.clone_from(s),
+                _ => return anyhow::Result::Err(anyhow::anyhow!("invalid str option {}", key)),
+            },
+            Value::Number(n) => match key.as_str() {
+                "parallelism" => {
+                    if let Some(n) = n.as_i64() {
+                        cfg.max_parallel_workers = n as u32;
+                    }
+                }
+                "refresh_interval" => {
+                    if let Some(n) = n.as_i64() {
+                        cfg.wait_between_batches_seconds = n as u32;
+                    }
+                }
+                "num_rows_per_partition" => {
+                    if let Some(n) = n.as_i64() {
+                        cfg.num_rows_per_partition = n as u32;
+                    }
+                }
+                _ => return anyhow::Result::Err(anyhow::anyhow!("invalid num option {}", key)),
+            },
+            Value::Bool(v) => {
+                if key == "initial_copy_only" {
+                    cfg.initial_copy_only = *v;
+                } else if key == "setup_watermark_table_on_destination" {
+                    cfg.setup_watermark_table_on_destination = *v;
+                } else if key == "dst_table_full_resync" {
+                    cfg.dst_table_full_resync = *v;
+                } else {
+                    return anyhow::Result::Err(anyhow::anyhow!("invalid bool option {}", key));
+                }
+            }
+            _ => {
+                tracing::info!("ignoring option {} with value {:?}", key, value);
+            }
+        }
+    }
+    if !cfg.initial_copy_only {
+        if let Some(QRepWriteMode {
+            write_type: wt,
+            upsert_key_columns: _,
+        }) = cfg.write_mode
+        {
+            if wt == QRepWriteType::QrepWriteModeOverwrite as i32 {
+                return anyhow::Result::Err(anyhow::anyhow!(
+                    "write mode overwrite can only be set with initial_copy_only = true"
+                ));
+            }
+        }
+    }
+    Ok(cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use pt::flow_model::{FlowJobColumnSetting, FlowJobTableSettings};
+
+    use super::*;
+
+    fn cdc_job(table_mappings: Vec<FlowJobTableMapping>) -> FlowJob {
+        FlowJob {
+            name: "mirror".to_owned(),
+            source_peer: "src".to_owned(),
+            target_peer: "dst".to_owned(),
+            table_mappings,
+            do_initial_copy: true,
+            publication_name: None,
+            snapshot_num_rows_per_partition: None,
+            snapshot_max_parallel_workers: None,
+            snapshot_num_tables_in_parallel: None,
+            snapshot_staging_path: String::new(),
+            cdc_staging_path: None,
+            replication_slot_name: None,
+            max_batch_size: None,
+            sync_interval: None,
+            resync: false,
+            soft_delete_col_name: None,
+            synced_at_col_name: None,
+            initial_snapshot_only: false,
+            script: String::new(),
+            system: "Q".to_owned(),
+            disable_peerdb_columns: false,
+            tags: HashMap::new(),
+            env: HashMap::new(),
+        }
+    }
+
+    fn mapping(settings: FlowJobTableSettings) -> FlowJobTableMapping {
+        FlowJobTableMapping {
+            source_table_identifier: "public.users".to_owned(),
+            destination_table_identifier: "users".to_owned(),
+            partition_key: Some("id".to_owned()),
+            exclude: vec!["secret".to_owned()],
+            settings,
+        }
+    }
+
+    #[test]
+    fn table_engine_names() {
+        assert_eq!(
+            table_engine("merge_tree").unwrap(),
+            TableEngine::ChEngineMergeTree
+        );
+        assert_eq!(
+            table_engine("Replicated_Replacing_Merge_Tree").unwrap(),
+            TableEngine::ChEngineReplicatedReplacingMergeTree
+        );
+        assert!(table_engine("CH_ENGINE_MERGE_TREE").is_err());
+        assert!(table_engine("log").is_err());
+    }
+
+    #[test]
+    fn cdc_flow_config_table_settings() {
+        let settings = FlowJobTableSettings {
+            columns: vec![
+                FlowJobColumnSetting {
+                    source_name: "id".to_owned(),
+                    destination_name: None,
+                    destination_type: None,
+                    ordering: 1,
+                    nullable: false,
+                },
+                FlowJobColumnSetting {
+                    source_name: "name".to_owned(),
+                    destination_name: Some("full_name".to_owned()),
+                    destination_type: Some("String".to_owned()),
+                    ordering: 0,
+                    nullable: true,
+                },
+            ],
+            engine: Some("merge_tree".to_owned()),
+            sharding_key: Some("id".to_owned()),
+            policy_name: Some("hot_cold".to_owned()),
+            soft_delete_col_name: Some("_deleted".to_owned()),
+        };
+        let job = cdc_job(vec![mapping(settings)]);
+        let config = cdc_flow_config(&job, "src".to_owned(), "dst".to_owned()).unwrap();
+
+        assert_eq!(
+            config.table_mappings,
+            vec![pt::peerdb_flow::TableMapping {
+                source_table_identifier: "public.users".to_owned(),
+                destination_table_identifier: "users".to_owned(),
+                partition_key: "id".to_owned(),
+                exclude: vec!["secret".to_owned()],
+                columns: vec![
+                    pt::peerdb_flow::ColumnSetting {
+                        source_name: "id".to_owned(),
+                        destination_name: String::new(),
+                        destination_type: String::new(),
+                        ordering: 1,
+                        nullable_enabled: false,
+                    },
+                    pt::peerdb_flow::ColumnSetting {
+                        source_name: "name".to_owned(),
+                        destination_name: "full_name".to_owned(),
+                        destination_type: "String".to_owned(),
+                        ordering: 0,
+                        nullable_enabled: true,
+                    },
+                ],
+                engine: TableEngine::ChEngineMergeTree as i32,
+                sharding_key: "id".to_owned(),
+                policy_name: "hot_cold".to_owned(),
+                soft_delete_col_name: "_deleted".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn cdc_flow_config_default_table_settings() {
+        let job = cdc_job(vec![mapping(FlowJobTableSettings::default())]);
+        let config = cdc_flow_config(&job, "src".to_owned(), "dst".to_owned()).unwrap();
+
+        let [table_mapping] = config.table_mappings.as_slice() else {
+            panic!(
+                "expected one table mapping, got {:?}",
+                config.table_mappings
+            );
+        };
+        assert!(table_mapping.columns.is_empty());
+        assert_eq!(
+            table_mapping.engine,
+            TableEngine::ChEngineReplacingMergeTree as i32
+        );
+        assert_eq!(table_mapping.sharding_key, "");
+        assert_eq!(table_mapping.soft_delete_col_name, "");
+    }
+
+    #[test]
+    fn cdc_flow_config_unknown_engine() {
+        let job = cdc_job(vec![mapping(FlowJobTableSettings {
+            engine: Some("log".to_owned()),
+            ..Default::default()
+        })]);
+        assert!(cdc_flow_config(&job, "src".to_owned(), "dst".to_owned()).is_err());
+    }
+}
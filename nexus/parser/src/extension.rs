@@ -0,0 +1,1199 @@
+// Nexus statements which are not understood by the sqlparser fork.
+//
+// `parse_sql` splits the input into statements the same way
+// `Parser::parse_statements` does, but offers every statement to the nexus
+// parsers first. These only consume tokens once the leading words match,
+// otherwise the statement is left for sqlparser to parse.
+
+use std::collections::HashMap;
+
+use analyzer::{MirrorAlteration, Password, PeerDDL};
+use pt::flow_model::{FlowJobColumnSetting, FlowJobTableMapping, FlowJobTableSettings};
+use sqlparser::{
+    ast::{Expr, Statement, Value},
+    dialect::Dialect,
+    keywords::Keyword,
+    parser::{Parser, ParserError},
+    tokenizer::Token,
+};
+
+#[derive(Debug)]
+pub enum ParsedStatement {
+    Sql(Statement),
+    /// `CREATE OR REPLACE PEER`, parsed as the CREATE PEER statement the peer is replaced with
+    CreateOrReplacePeer(Statement),
+    Nexus(PeerDDL),
+}
+
+pub fn parse_sql(dialect: &dyn Dialect, sql: &str) -> Result<Vec<ParsedStatement>, ParserError> {
+    let mut parser = Parser::new(dialect).try_with_sql(sql)?;
+    let mut stmts = Vec::new();
+    let mut expecting_statement_delimiter = false;
+    loop {
+        // ignore empty statements (between successive statement delimiters)
+        while parser.consume_token(&Token::SemiColon) {
+            expecting_statement_delimiter = false;
+        }
+
+        if parser.peek_token().token == Token::EOF {
+            break;
+        }
+        if expecting_statement_delimiter {
+            return parser.expected("end of statement", parser.peek_token());
+        }
+
+        let stmt = if let Some(ddl) = parse_nexus_statement(&mut parser, dialect)? {
+            ParsedStatement::Nexus(ddl)
+        } else if parse_words(&mut parser, &["CREATE", "OR", "REPLACE", "PEER"]) {
+            let create_peer = parse_as_create(&mut parser, dialect, "PEER")?;
+            if matches!(
+                create_peer,
+                Statement::CreatePeer {
+                    if_not_exists: true,
+                    ..
+                }
+            ) {
+                return Err(ParserError::ParserError(
+                    "OR REPLACE and IF NOT EXISTS cannot be used together".to_owned(),
+                ));
+            }
+            ParsedStatement::CreateOrReplacePeer(create_peer)
+        } else if parse_words(&mut parser, &["CREATE", "MIRROR"]) {
+            ParsedStatement::Sql(parse_as_create(&mut parser, dialect, "MIRROR")?)
+        } else {
+            ParsedStatement::Sql(parser.parse_statement()?)
+        };
+        stmts.push(stmt);
+        expecting_statement_delimiter = true;
+    }
+    Ok(stmts)
+}
+
+fn parse_nexus_statement(
+    parser: &mut Parser,
+    dialect: &dyn Dialect,
+) -> Result<Option<PeerDDL>, ParserError> {
+    if parse_words(parser, &["CREATE", "USER"]) {
+        let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let user_name = parse_user_name(parser)?;
+        let password = parse_password(parser)?;
+        Ok(Some(PeerDDL::CreateUser {
+            if_not_exists,
+            user_name,
+            password,
+        }))
+    } else if parse_words(parser, &["ALTER", "USER"]) {
+        let user_name = parse_user_name(parser)?;
+        let password = parse_password(parser)?;
+        Ok(Some(PeerDDL::AlterUser {
+            user_name,
+            password,
+        }))
+    } else if parse_words(parser, &["DROP", "USER"]) {
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let user_name = parse_user_name(parser)?;
+        Ok(Some(PeerDDL::DropUser {
+            if_exists,
+            user_name,
+        }))
+    } else if parse_words(parser, &["ALTER", "PEER"]) {
+        let peer_name = parser.parse_identifier(false)?.value.to_lowercase();
+        let options = parser.parse_options(Keyword::SET)?;
+        if options.is_empty() {
+            return parser.expected("SET (option = value, ...)", parser.peek_token());
+        }
+        Ok(Some(PeerDDL::AlterPeer { peer_name, options }))
+    } else if parse_words(parser, &["ALTER", "MIRROR"]) {
+        let flow_job_name = parser.parse_identifier(false)?.value.to_lowercase();
+        let alteration = parse_mirror_alteration(parser)?;
+        Ok(Some(PeerDDL::AlterMirror {
+            flow_job_name,
+            alteration,
+        }))
+    } else if parse_words(parser, &["DESCRIBE", "MIRROR"])
+        || parse_words(parser, &["SHOW", "MIRROR", "STATUS"])
+    {
+        let flow_job_name = parser.parse_identifier(false)?.value.to_lowercase();
+        Ok(Some(PeerDDL::DescribeMirror { flow_job_name }))
+    } else if parse_words(parser, &["VALIDATE", "PEER"]) {
+        let create_peer = parse_as_create(parser, dialect, "PEER")?;
+        Ok(Some(PeerDDL::ValidatePeer {
+            create_peer: Box::new(create_peer),
+        }))
+    } else if parse_words(parser, &["VALIDATE", "MIRROR"]) {
+        let create_mirror = parse_as_create(parser, dialect, "MIRROR")?;
+        Ok(Some(PeerDDL::ValidateMirror {
+            create_mirror: Box::new(create_mirror),
+        }))
+    } else if parse_words(parser, &["EXPLAIN", "CREATE", "MIRROR"]) {
+        let create_mirror = parse_as_create(parser, dialect, "MIRROR")?;
+        Ok(Some(PeerDDL::ExplainMirror {
+            create_mirror: Box::new(create_mirror),
+        }))
+    } else if parse_words(parser, &["CREATE", "ALERT"]) {
+        let options = parser.parse_options(Keyword::WITH)?;
+        let config = analyzer::parse_alert_config(&options)
+            .map_err(|err| ParserError::ParserError(err.to_string()))?;
+        Ok(Some(PeerDDL::CreateAlert {
+            config: Box::new(config),
+        }))
+    } else if parse_words(parser, &["DROP", "ALERT"]) {
+        let id = parser.parse_literal_uint()?;
+        let id = i32::try_from(id)
+            .map_err(|_| ParserError::ParserError(format!("invalid alert id {id}")))?;
+        Ok(Some(PeerDDL::DropAlert { id }))
+    } else if parse_words(parser, &["SHOW", "ALERTS"]) {
+        Ok(Some(PeerDDL::ShowAlerts))
+    } else if parse_words(parser, &["SHOW", "PEERDB", "SETTINGS"]) {
+        Ok(Some(PeerDDL::ShowSettings))
+    } else if parse_words(parser, &["ALTER", "SYSTEM", "SET"]) {
+        let name = parse_setting_name(parser)?;
+        if !parser.consume_token(&Token::Eq) && !parser.parse_keyword(Keyword::TO) {
+            return parser.expected("= or TO", parser.peek_token());
+        }
+        // parse_value only takes unsigned numbers
+        let negative = parser.consume_token(&Token::Minus);
+        let value = match parser.parse_value()? {
+            Value::Number(n, _) if negative => format!("-{n}"),
+            Value::SingleQuotedString(value) if !negative => value,
+            value @ (Value::Number(..) | Value::Boolean(_)) if !negative => value.to_string(),
+            value => {
+                return Err(ParserError::ParserError(format!(
+                    "invalid value {value} for setting {name}"
+                )));
+            }
+        };
+        Ok(Some(PeerDDL::AlterSetting { name, value }))
+    } else if parse_words(parser, &["CREATE", "SCRIPT"]) {
+        parse_create_script(parser, false)
+    } else if parse_words(parser, &["CREATE", "OR", "REPLACE", "SCRIPT"]) {
+        parse_create_script(parser, true)
+    } else if parse_words(parser, &["DROP", "SCRIPT"]) {
+        let if_exists = parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let script_name = parser.parse_identifier(false)?.value;
+        Ok(Some(PeerDDL::DropScript {
+            if_exists,
+            script_name,
+        }))
+    } else if parse_words(parser, &["SHOW", "SCRIPTS"]) {
+        Ok(Some(PeerDDL::ShowScripts))
+    } else if parse_words(parser, &["ENTER", "MAINTENANCE"]) {
+        Ok(Some(PeerDDL::EnterMaintenance))
+    } else if parse_words(parser, &["EXIT", "MAINTENANCE"]) {
+        Ok(Some(PeerDDL::ExitMaintenance))
+    } else if parse_words(parser, &["SHOW", "MAINTENANCE", "STATUS"]) {
+        Ok(Some(PeerDDL::ShowMaintenanceStatus))
+    } else if parse_words(parser, &["SHOW", "PEERS"]) {
+        Ok(Some(PeerDDL::ShowPeers))
+    } else if parse_words(parser, &["SHOW", "MIRRORS"]) {
+        let tags = if parse_words(parser, &["WITH", "TAGS"]) {
+            parse_tag_list(parser)?
+        } else {
+            HashMap::new()
+        };
+        Ok(Some(PeerDDL::ShowMirrors { tags }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Consumes the upcoming tokens if they are the unquoted `words`, compared
+/// case-insensitively, so that words which are not sqlparser keywords work too.
+fn parse_words(parser: &mut Parser, words: &[&str]) -> bool {
+    let matched = words
+        .iter()
+        .enumerate()
+        .all(|(idx, word)| is_word(&parser.peek_nth_token(idx).token, word));
+    if matched {
+        for _ in words {
+            parser.next_token();
+        }
+    }
+    matched
+}
+
+/// Whether the token is the unquoted `word`, compared case-insensitively.
+fn is_word(token: &Token, word: &str) -> bool {
+    matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word))
+}
+
+/// Parses the rest of the statement as `CREATE <object> ...`, so that VALIDATE and EXPLAIN
+/// take the same definitions as the CREATE statement they are about.
+fn parse_as_create(
+    parser: &mut Parser,
+    dialect: &dyn Dialect,
+    object: &str,
+) -> Result<Statement, ParserError> {
+    let mut tokens = vec![Token::make_keyword("CREATE"), Token::make_keyword(object)];
+    while !matches!(parser.peek_token().token, Token::SemiColon | Token::EOF) {
+        tokens.push(parser.next_token().token);
+    }
+    if object == "MIRROR" {
+        tokens = lower_table_settings(tokens, dialect)?;
+    }
+
+    let mut create_parser = Parser::new(dialect).with_tokens(tokens);
+    let stmt = create_parser.parse_statement()?;
+    if create_parser.peek_token().token != Token::EOF {
+        return create_parser.expected("end of statement", create_parser.peek_token());
+    }
+    Ok(stmt)
+}
+
+/// The sqlparser fork only knows `source:destination` and `{from: .., to: ..}` in the TABLE
+/// MAPPING of CREATE MIRROR, so the settings after each mapping are taken out of the tokens
+/// here. They are handed to the analyzer in the table_settings option, a json array with
+/// the settings of every mapping in order.
+fn lower_table_settings(
+    tokens: Vec<Token>,
+    dialect: &dyn Dialect,
+) -> Result<Vec<Token>, ParserError> {
+    let Some(start) = tokens.windows(3).position(|window| {
+        is_word(&window[0], "TABLE") && is_word(&window[1], "MAPPING") && window[2] == Token::LParen
+    }) else {
+        return Ok(tokens);
+    };
+
+    let mut lowered = tokens[..start + 3].to_vec();
+    let mut rest = tokens[start + 3..].iter();
+    let mut table_settings = Vec::new();
+    let mut clause = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let Some(token) = rest.next() else {
+            return Err(ParserError::ParserError(
+                "expected ) at the end of the table mapping".to_owned(),
+            ));
+        };
+        if depth == 0 && matches!(token, Token::Comma | Token::RParen) {
+            table_settings.push(parse_clause(std::mem::take(&mut clause), dialect)?);
+            lowered.push(token.clone());
+            if *token == Token::RParen {
+                break;
+            }
+            continue;
+        }
+        // a table named like the clauses still follows the colon or dot of the mapping
+        let starts_clause = depth == 0
+            && (is_word(token, "COLUMNS") || is_word(token, "WITH"))
+            && !matches!(lowered.last(), Some(Token::Colon | Token::Period));
+        match token {
+            Token::LParen | Token::LBrace | Token::LBracket => depth += 1,
+            Token::RParen | Token::RBrace | Token::RBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if clause.is_empty() && !starts_clause {
+            lowered.push(token.clone());
+        } else {
+            clause.push(token.clone());
+        }
+    }
+    let options: Vec<Token> = rest.cloned().collect();
+
+    if options
+        .windows(2)
+        .any(|window| is_word(&window[0], "table_settings") && window[1] == Token::Eq)
+    {
+        return Err(ParserError::ParserError(
+            "table_settings cannot be set, table settings follow their table mapping".to_owned(),
+        ));
+    }
+    if table_settings
+        .iter()
+        .all(|settings| *settings == FlowJobTableSettings::default())
+    {
+        lowered.extend(options);
+        return Ok(lowered);
+    }
+
+    let table_settings = serde_json::to_string(&table_settings)
+        .map_err(|err| ParserError::ParserError(err.to_string()))?;
+    let option = [
+        Token::make_word("table_settings", None),
+        Token::Eq,
+        Token::SingleQuotedString(table_settings),
+    ];
+    match options.as_slice() {
+        [with, Token::LParen, Token::RParen, tail @ ..] if is_word(with, "WITH") => {
+            lowered.extend([with.clone(), Token::LParen]);
+            lowered.extend(option);
+            lowered.push(Token::RParen);
+            lowered.extend(tail.iter().cloned());
+        }
+        [with, Token::LParen, ..] if is_word(with, "WITH") => {
+            lowered.extend([with.clone(), Token::LParen]);
+            lowered.extend(option);
+            lowered.push(Token::Comma);
+            lowered.extend(options[2..].iter().cloned());
+        }
+        _ => {
+            lowered.extend(options);
+            lowered.extend([Token::make_keyword("WITH"), Token::LParen]);
+            lowered.extend(option);
+            lowered.push(Token::RParen);
+        }
+    }
+    Ok(lowered)
+}
+
+fn parse_clause(
+    clause: Vec<Token>,
+    dialect: &dyn Dialect,
+) -> Result<FlowJobTableSettings, ParserError> {
+    let mut parser = Parser::new(dialect).with_tokens(clause);
+    let settings = parse_table_settings(&mut parser)?;
+    if parser.peek_token().token != Token::EOF {
+        return parser.expected("end of table mapping", parser.peek_token());
+    }
+    Ok(settings)
+}
+
+// ADD TABLE source:destination [PARTITION KEY column] [table settings], ...
+// | REMOVE TABLE source, ...
+// | SET (option = value, ...)
+fn parse_mirror_alteration(parser: &mut Parser) -> Result<MirrorAlteration, ParserError> {
+    if parser.parse_keywords(&[Keyword::ADD, Keyword::TABLE]) {
+        let tables = parser.parse_comma_separated(parse_table_mapping)?;
+        Ok(MirrorAlteration::AddTables(tables))
+    } else if parse_words(parser, &["REMOVE", "TABLE"]) {
+        let tables = parser.parse_comma_separated(|parser| parser.parse_object_name(false))?;
+        Ok(MirrorAlteration::RemoveTables(
+            tables.iter().map(|table| table.to_string()).collect(),
+        ))
+    } else if parse_words(parser, &["SET", "TAGS"]) {
+        Ok(MirrorAlteration::SetTags(parse_tag_list(parser)?))
+    } else {
+        let options = parser.parse_options(Keyword::SET)?;
+        if options.is_empty() {
+            return parser.expected("ADD TABLE, REMOVE TABLE or SET", parser.peek_token());
+        }
+        let config_update = analyzer::parse_cdc_config_update(&options)
+            .map_err(|err| ParserError::ParserError(err.to_string()))?;
+        Ok(MirrorAlteration::Set(Box::new(config_update)))
+    }
+}
+
+fn parse_table_mapping(parser: &mut Parser) -> Result<FlowJobTableMapping, ParserError> {
+    let source = parser.parse_object_name(false)?;
+    parser.expect_token(&Token::Colon)?;
+    let destination = parser.parse_object_name(false)?;
+    let partition_key = if parser.parse_keywords(&[Keyword::PARTITION, Keyword::KEY]) {
+        Some(parser.parse_identifier(false)?.value)
+    } else {
+        None
+    };
+    Ok(FlowJobTableMapping {
+        source_table_identifier: source.to_string(),
+        destination_table_identifier: destination.to_string(),
+        partition_key,
+        exclude: vec![],
+        settings: parse_table_settings(parser)?,
+    })
+}
+
+// [COLUMNS (column [AS name] [TYPE 'type'] [ORDERING n] [NULLABLE], ...)]
+// [WITH (engine = 'merge_tree', sharding_key = '..', policy_name = '..',
+//        soft_delete_col_name = '..')]
+fn parse_table_settings(parser: &mut Parser) -> Result<FlowJobTableSettings, ParserError> {
+    let mut settings = FlowJobTableSettings::default();
+    if parse_words(parser, &["COLUMNS"]) {
+        parser.expect_token(&Token::LParen)?;
+        settings.columns = parser.parse_comma_separated(parse_column_setting)?;
+        parser.expect_token(&Token::RParen)?;
+    }
+    for option in parser.parse_options(Keyword::WITH)? {
+        let Expr::Value(Value::SingleQuotedString(value)) = option.value else {
+            return Err(ParserError::ParserError(format!(
+                "table setting {} must be a string",
+                option.name
+            )));
+        };
+        let setting = match option.name.value.to_lowercase().as_str() {
+            "engine" => &mut settings.engine,
+            "sharding_key" => &mut settings.sharding_key,
+            "policy_name" => &mut settings.policy_name,
+            "soft_delete_col_name" => &mut settings.soft_delete_col_name,
+            _ => {
+                return Err(ParserError::ParserError(format!(
+                    "unknown table setting {}",
+                    option.name
+                )));
+            }
+        };
+        *setting = Some(value);
+    }
+    Ok(settings)
+}
+
+fn parse_column_setting(parser: &mut Parser) -> Result<FlowJobColumnSetting, ParserError> {
+    let source_name = parser.parse_identifier(false)?.value;
+    let destination_name = if parser.parse_keyword(Keyword::AS) {
+        Some(parser.parse_identifier(false)?.value)
+    } else {
+        None
+    };
+    let destination_type = if parse_words(parser, &["TYPE"]) {
+        Some(parse_string_literal(parser)?)
+    } else {
+        None
+    };
+    // position in the ClickHouse ordering key
+    let ordering = if parse_words(parser, &["ORDERING"]) {
+        let ordering = parser.parse_literal_uint()?;
+        i32::try_from(ordering)
+            .map_err(|_| ParserError::ParserError(format!("invalid ordering {ordering}")))?
+    } else {
+        0
+    };
+    let nullable = parse_words(parser, &["NULLABLE"]);
+    Ok(FlowJobColumnSetting {
+        source_name,
+        destination_name,
+        destination_type,
+        ordering,
+        nullable,
+    })
+}
+
+// (key = 'value', ...), an empty list is allowed to clear the tags
+fn parse_tag_list(parser: &mut Parser) -> Result<HashMap<String, String>, ParserError> {
+    parser.expect_token(&Token::LParen)?;
+    if parser.consume_token(&Token::RParen) {
+        return Ok(HashMap::new());
+    }
+    let options = parser.parse_comma_separated(Parser::parse_sql_option)?;
+    parser.expect_token(&Token::RParen)?;
+    options
+        .into_iter()
+        .map(|option| {
+            let value = match option.value {
+                Expr::Value(Value::SingleQuotedString(value)) => value,
+                Expr::Value(value @ (Value::Number(..) | Value::Boolean(_))) => value.to_string(),
+                value => {
+                    return Err(ParserError::ParserError(format!(
+                        "tag {} must be a string, got {value}",
+                        option.name
+                    )));
+                }
+            };
+            Ok((option.name.value, value))
+        })
+        .collect()
+}
+
+// name LANGUAGE lua AS $$...$$, the name is kept as written since mirrors refer to
+// scripts with a string
+fn parse_create_script(
+    parser: &mut Parser,
+    or_replace: bool,
+) -> Result<Option<PeerDDL>, ParserError> {
+    let script_name = parser.parse_identifier(false)?.value;
+    parser.expect_keyword(Keyword::LANGUAGE)?;
+    let lang = parser.parse_identifier(false)?.value.to_lowercase();
+    // the catalog only knows lua scripts
+    if lang != "lua" {
+        return Err(ParserError::ParserError(format!(
+            "unsupported script language {lang}, expected lua"
+        )));
+    }
+    parser.expect_keyword(Keyword::AS)?;
+    let source = match parser.parse_value()? {
+        Value::SingleQuotedString(source) => source,
+        Value::DollarQuotedString(source) => source.value,
+        _ => return parser.expected("the script source as a string", parser.peek_token()),
+    };
+    Ok(Some(PeerDDL::CreateScript {
+        or_replace,
+        script_name,
+        lang,
+        source,
+    }))
+}
+
+// peerdb.cdc_channel_buffer_size is the flow service's PEERDB_CDC_CHANNEL_BUFFER_SIZE
+fn parse_setting_name(parser: &mut Parser) -> Result<String, ParserError> {
+    let name = parser.parse_object_name(false)?;
+    match name.0.as_slice() {
+        [prefix, setting] if prefix.value.eq_ignore_ascii_case("peerdb") => {
+            Ok(format!("PEERDB_{}", setting.value.to_uppercase()))
+        }
+        _ => Err(ParserError::ParserError(format!(
+            "only peerdb settings can be set, expected peerdb.<name> but got {name}"
+        ))),
+    }
+}
+
+// user names follow postgres rules, unquoted names are folded to lowercase
+fn parse_user_name(parser: &mut Parser) -> Result<String, ParserError> {
+    let ident = parser.parse_identifier(false)?;
+    Ok(if ident.quote_style.is_some() {
+        ident.value
+    } else {
+        ident.value.to_lowercase()
+    })
+}
+
+// [WITH] PASSWORD 'password'
+fn parse_password(parser: &mut Parser) -> Result<Password, ParserError> {
+    parser.parse_keyword(Keyword::WITH);
+    parser.expect_keyword(Keyword::PASSWORD)?;
+    parse_string_literal(parser).map(Password)
+}
+
+fn parse_string_literal(parser: &mut Parser) -> Result<String, ParserError> {
+    let token = parser.next_token();
+    match token.token {
+        Token::SingleQuotedString(s) | Token::EscapedStringLiteral(s) => Ok(s),
+        Token::DollarQuotedString(s) => Ok(s.value),
+        _ => parser.expected("a string literal", token),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use analyzer::{PeerDDLAnalyzer, StatementAnalyzer};
+    use sqlparser::dialect::PostgreSqlDialect;
+
+    use super::*;
+
+    fn parse_one(sql: &str) -> ParsedStatement {
+        let mut stmts = parse_sql(&PostgreSqlDialect {}, sql).unwrap();
+        assert_eq!(stmts.len(), 1);
+        stmts.remove(0)
+    }
+
+    #[test]
+    fn create_user() {
+        let stmt = parse_one("CREATE USER IF NOT EXISTS Alice WITH PASSWORD 'secret'");
+        assert!(matches!(
+            stmt,
+            ParsedStatement::Nexus(PeerDDL::CreateUser {
+                if_not_exists: true,
+                user_name,
+                password,
+            }) if user_name == "alice" && &*password == "secret"
+        ));
+    }
+
+    #[test]
+    fn alter_user_keeps_quoted_name() {
+        let stmt = parse_one(r#"ALTER USER "Alice" PASSWORD 'changed'"#);
+        assert!(matches!(
+            stmt,
+            ParsedStatement::Nexus(PeerDDL::AlterUser { user_name, password })
+                if user_name == "Alice" && &*password == "changed"
+        ));
+    }
+
+    #[test]
+    fn drop_user() {
+        let stmt = parse_one("DROP USER IF EXISTS alice");
+        assert!(matches!(
+            stmt,
+            ParsedStatement::Nexus(PeerDDL::DropUser {
+                if_exists: true,
+                user_name,
+            }) if user_name == "alice"
+        ));
+    }
+
+    #[test]
+    fn user_statements_manage_users() {
+        for sql in [
+            "CREATE USER alice PASSWORD 'secret'",
+            "ALTER USER alice WITH PASSWORD 'secret'",
+            "DROP USER alice",
+        ] {
+            let ParsedStatement::Nexus(ddl) = parse_one(sql) else {
+                panic!("{sql} was not parsed as a nexus statement");
+            };
+            assert!(ddl.manages_users(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn alter_system_set_negative_number() {
+        for (sql, expected) in [
+            ("ALTER SYSTEM SET peerdb.cdc_idle_timeout = -1", "-1"),
+            ("ALTER SYSTEM SET peerdb.cdc_idle_timeout TO 10", "10"),
+            ("ALTER SYSTEM SET peerdb.cdc_idle_timeout = '-1'", "-1"),
+        ] {
+            assert!(
+                matches!(
+                    parse_one(sql),
+                    ParsedStatement::Nexus(PeerDDL::AlterSetting { value, .. }) if value == expected
+                ),
+                "{sql}"
+            );
+        }
+        assert!(parse_sql(&PostgreSqlDialect {}, "ALTER SYSTEM SET peerdb.a = -'1'").is_err());
+    }
+
+    #[test]
+    fn create_user_requires_password() {
+        assert!(parse_sql(&PostgreSqlDialect {}, "CREATE USER alice").is_err());
+    }
+
+    const PEER_OPTIONS: &str = "FROM POSTGRES WITH (host = 'localhost', port = 5432, \
+                                user = 'postgres', password = 'postgres', database = 'postgres')";
+
+    #[test]
+    fn create_or_replace_peer() {
+        let sql = format!("CREATE OR REPLACE PEER pg {PEER_OPTIONS}");
+        let ParsedStatement::CreateOrReplacePeer(stmt) = parse_one(&sql) else {
+            panic!("{sql} was not parsed as CREATE OR REPLACE PEER");
+        };
+        assert!(matches!(
+            PeerDDLAnalyzer.analyze(&stmt).unwrap(),
+            Some(PeerDDL::CreatePeer {
+                peer,
+                if_not_exists: false,
+                ..
+            }) if peer.name == "pg"
+        ));
+    }
+
+    #[test]
+    fn create_peer_if_not_exists() {
+        let sql = format!("CREATE PEER IF NOT EXISTS pg {PEER_OPTIONS}");
+        let ParsedStatement::Sql(stmt) = parse_one(&sql) else {
+            panic!("{sql} was not parsed by sqlparser");
+        };
+        assert!(matches!(
+            PeerDDLAnalyzer.analyze(&stmt).unwrap(),
+            Some(PeerDDL::CreatePeer {
+                if_not_exists: true,
+                or_replace: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn create_or_replace_peer_errors() {
+        let sql = format!("CREATE OR REPLACE PEER IF NOT EXISTS pg {PEER_OPTIONS}");
+        let err = parse_sql(&PostgreSqlDialect {}, &sql).unwrap_err();
+        assert!(err.to_string().contains("cannot be used together"), "{err}");
+
+        // bad options are reported by the analyzer, like they are for CREATE PEER
+        let sql = "CREATE OR REPLACE PEER pg FROM POSTGRES WITH (host = 'localhost')";
+        let ParsedStatement::CreateOrReplacePeer(stmt) = parse_one(sql) else {
+            panic!("{sql} was not parsed as CREATE OR REPLACE PEER");
+        };
+        assert!(PeerDDLAnalyzer.analyze(&stmt).is_err());
+    }
+
+    fn analyze_cdc_mirror(sql: &str) -> pt::flow_model::FlowJob {
+        let ParsedStatement::Sql(stmt) = parse_one(sql) else {
+            panic!("{sql} was not parsed by sqlparser");
+        };
+        match PeerDDLAnalyzer.analyze(&stmt).unwrap() {
+            Some(PeerDDL::CreateMirrorForCDC { flow_job, .. }) => *flow_job,
+            ddl => panic!("{sql} was analyzed as {ddl:?}"),
+        }
+    }
+
+    #[test]
+    fn create_mirror_table_settings() {
+        let flow_job = analyze_cdc_mirror(
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a, \
+             public.b:b COLUMNS (id ORDERING 1, name AS full_name TYPE 'String' NULLABLE) \
+             WITH (engine = 'merge_tree', sharding_key = 'id', soft_delete_col_name = '_deleted')) \
+             WITH (do_initial_copy = true)",
+        );
+        assert!(flow_job.do_initial_copy);
+        let [a, b] = flow_job.table_mappings.as_slice() else {
+            panic!(
+                "expected two table mappings, got {:?}",
+                flow_job.table_mappings
+            );
+        };
+        assert_eq!(a.settings, FlowJobTableSettings::default());
+        assert_eq!(b.source_table_identifier, "public.b");
+        assert_eq!(
+            b.settings,
+            FlowJobTableSettings {
+                columns: vec![
+                    FlowJobColumnSetting {
+                        source_name: "id".to_owned(),
+                        destination_name: None,
+                        destination_type: None,
+                        ordering: 1,
+                        nullable: false,
+                    },
+                    FlowJobColumnSetting {
+                        source_name: "name".to_owned(),
+                        destination_name: Some("full_name".to_owned()),
+                        destination_type: Some("String".to_owned()),
+                        ordering: 0,
+                        nullable: true,
+                    },
+                ],
+                engine: Some("merge_tree".to_owned()),
+                sharding_key: Some("id".to_owned()),
+                policy_name: None,
+                soft_delete_col_name: Some("_deleted".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn validate_mirror_table_settings() {
+        let stmt = parse_one(
+            "VALIDATE MIRROR m FROM src TO dst WITH TABLE MAPPING ( \
+             {from: public.a, to: a, key: id} WITH (policy_name = 'hot_cold')) \
+             WITH (do_initial_copy = true)",
+        );
+        let ParsedStatement::Nexus(PeerDDL::ValidateMirror { create_mirror }) = stmt else {
+            panic!("VALIDATE MIRROR was parsed as {stmt:?}");
+        };
+        let Some(PeerDDL::CreateMirrorForCDC { flow_job, .. }) =
+            PeerDDLAnalyzer.analyze(&create_mirror).unwrap()
+        else {
+            panic!("{create_mirror} is not a CDC mirror");
+        };
+        let [mapping] = flow_job.table_mappings.as_slice() else {
+            panic!(
+                "expected one table mapping, got {:?}",
+                flow_job.table_mappings
+            );
+        };
+        assert_eq!(mapping.partition_key.as_deref(), Some("id"));
+        assert_eq!(mapping.settings.policy_name.as_deref(), Some("hot_cold"));
+    }
+
+    #[test]
+    fn create_mirror_without_table_settings() {
+        let flow_job = analyze_cdc_mirror(
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a, public.b:columns) \
+             WITH (do_initial_copy = true)",
+        );
+        assert_eq!(flow_job.table_mappings.len(), 2);
+        assert_eq!(
+            flow_job.table_mappings[1].destination_table_identifier,
+            "columns"
+        );
+        assert!(
+            flow_job
+                .table_mappings
+                .iter()
+                .all(|mapping| mapping.settings == FlowJobTableSettings::default())
+        );
+    }
+
+    #[test]
+    fn create_mirror_table_settings_errors() {
+        for sql in [
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a WITH (engine = 1)) \
+             WITH (do_initial_copy = true)",
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a WITH (order = 'id')) \
+             WITH (do_initial_copy = true)",
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a WITH () COLUMNS (id)) \
+             WITH (do_initial_copy = true)",
+            "CREATE MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a) \
+             WITH (do_initial_copy = true, table_settings = '[{}]')",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn alter_mirror_add_table_settings() {
+        let stmt = parse_one(
+            "ALTER MIRROR m ADD TABLE public.a:a PARTITION KEY id, \
+             public.b:b COLUMNS (id ORDERING 1) WITH (engine = 'merge_tree')",
+        );
+        let ParsedStatement::Nexus(PeerDDL::AlterMirror {
+            flow_job_name,
+            alteration: MirrorAlteration::AddTables(tables),
+        }) = stmt
+        else {
+            panic!("ADD TABLE was parsed as {stmt:?}");
+        };
+        assert_eq!(flow_job_name, "m");
+        let [a, b] = tables.as_slice() else {
+            panic!("expected two tables, got {tables:?}");
+        };
+        assert_eq!(a.partition_key.as_deref(), Some("id"));
+        assert_eq!(a.settings, FlowJobTableSettings::default());
+        assert_eq!(b.destination_table_identifier, "b");
+        assert_eq!(b.settings.columns.len(), 1);
+        assert_eq!(b.settings.engine.as_deref(), Some("merge_tree"));
+    }
+
+    fn parse_alteration(sql: &str) -> MirrorAlteration {
+        match parse_one(sql) {
+            ParsedStatement::Nexus(PeerDDL::AlterMirror {
+                flow_job_name,
+                alteration,
+            }) if flow_job_name == "m" => alteration,
+            stmt => panic!("{sql} was parsed as {stmt:?}"),
+        }
+    }
+
+    #[test]
+    fn alter_mirror_remove_table() {
+        let MirrorAlteration::RemoveTables(tables) =
+            parse_alteration("ALTER MIRROR M REMOVE TABLE public.a, b")
+        else {
+            panic!("expected REMOVE TABLE");
+        };
+        assert_eq!(tables, ["public.a", "b"]);
+    }
+
+    #[test]
+    fn alter_mirror_set() {
+        let MirrorAlteration::Set(config_update) =
+            parse_alteration("ALTER MIRROR m SET (max_batch_size = 1000, sync_interval = 30)")
+        else {
+            panic!("expected SET");
+        };
+        assert_eq!(config_update.batch_size, 1000);
+        assert_eq!(config_update.idle_timeout, 30);
+        assert!(config_update.additional_tables.is_empty());
+    }
+
+    #[test]
+    fn alter_mirror_set_tags() {
+        let MirrorAlteration::SetTags(tags) =
+            parse_alteration("ALTER MIRROR m SET TAGS (team = 'data', tier = 1)")
+        else {
+            panic!("expected SET TAGS");
+        };
+        assert_eq!(
+            tags,
+            HashMap::from([
+                ("team".to_owned(), "data".to_owned()),
+                ("tier".to_owned(), "1".to_owned()),
+            ])
+        );
+
+        let MirrorAlteration::SetTags(tags) = parse_alteration("ALTER MIRROR m SET TAGS ()") else {
+            panic!("expected SET TAGS");
+        };
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn alter_mirror_errors() {
+        for sql in [
+            "ALTER MIRROR m",
+            "ALTER MIRROR m ADD TABLE public.a",
+            "ALTER MIRROR m SET ()",
+            "ALTER MIRROR m SET (do_initial_copy = true)",
+            "ALTER MIRROR m SET (max_batch_size = 'many')",
+            "ALTER MIRROR m SET (max_batch_size = 0)",
+            "ALTER MIRROR m SET (sync_interval = 86401)",
+            "ALTER MIRROR m SET TAGS (team = data)",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+
+    const CDC_MIRROR: &str = "MIRROR m FROM src TO dst WITH TABLE MAPPING (public.a:a) \
+                              WITH (do_initial_copy = true)";
+
+    #[test]
+    fn validate_peer() {
+        let sql = format!("VALIDATE PEER pg {PEER_OPTIONS}");
+        let ParsedStatement::Nexus(PeerDDL::ValidatePeer { create_peer }) = parse_one(&sql) else {
+            panic!("{sql} was not parsed as VALIDATE PEER");
+        };
+        assert!(matches!(
+            PeerDDLAnalyzer.analyze(&create_peer).unwrap(),
+            Some(PeerDDL::CreatePeer { peer, .. }) if peer.name == "pg"
+        ));
+
+        // the options are only checked once the peer is validated
+        let sql = "VALIDATE PEER pg FROM POSTGRES WITH (host = 'localhost')";
+        assert!(matches!(
+            parse_one(sql),
+            ParsedStatement::Nexus(PeerDDL::ValidatePeer { .. })
+        ));
+    }
+
+    #[test]
+    fn validate_mirror() {
+        let sql = format!("VALIDATE {CDC_MIRROR}; SELECT 1");
+        let mut stmts = parse_sql(&PostgreSqlDialect {}, &sql).unwrap();
+        assert_eq!(stmts.len(), 2);
+        let ParsedStatement::Nexus(PeerDDL::ValidateMirror { create_mirror }) = stmts.remove(0)
+        else {
+            panic!("{sql} was not parsed as VALIDATE MIRROR");
+        };
+        assert!(matches!(
+            PeerDDLAnalyzer.analyze(&create_mirror).unwrap(),
+            Some(PeerDDL::CreateMirrorForCDC { flow_job, .. }) if flow_job.name == "m"
+        ));
+        assert!(matches!(
+            stmts[0],
+            ParsedStatement::Sql(Statement::Query(_))
+        ));
+    }
+
+    #[test]
+    fn validate_errors() {
+        for sql in [
+            "VALIDATE PEER",
+            "VALIDATE PEER pg FROM POSTGRES WITH (host = 'localhost') extra",
+            "VALIDATE MIRROR m FROM src",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn explain_create_mirror() {
+        let sql = format!("EXPLAIN CREATE {CDC_MIRROR}");
+        let ParsedStatement::Nexus(PeerDDL::ExplainMirror { create_mirror }) = parse_one(&sql)
+        else {
+            panic!("{sql} was not parsed as EXPLAIN CREATE MIRROR");
+        };
+        assert!(matches!(
+            PeerDDLAnalyzer.analyze(&create_mirror).unwrap(),
+            Some(PeerDDL::CreateMirrorForCDC { flow_job, .. }) if flow_job.do_initial_copy
+        ));
+
+        // other statements are still explained by sqlparser
+        assert!(matches!(
+            parse_one("EXPLAIN SELECT 1"),
+            ParsedStatement::Sql(Statement::Explain { .. })
+        ));
+        assert!(parse_sql(&PostgreSqlDialect {}, "EXPLAIN CREATE MIRROR m").is_err());
+    }
+
+    #[test]
+    fn create_alert() {
+        let sql = r#"CREATE ALERT WITH (service = 'Slack', config = '{"auth_token": "xoxb"}',
+                     mirrors = 'm1, m2')"#;
+        let ParsedStatement::Nexus(PeerDDL::CreateAlert { config }) = parse_one(sql) else {
+            panic!("{sql} was not parsed as CREATE ALERT");
+        };
+        assert_eq!(config.id, -1);
+        assert_eq!(config.service_type, "slack");
+        assert_eq!(config.service_config, r#"{"auth_token": "xoxb"}"#);
+        assert_eq!(config.alert_for_mirrors, ["m1", "m2"]);
+    }
+
+    #[test]
+    fn drop_and_show_alerts() {
+        assert!(matches!(
+            parse_one("DROP ALERT 7"),
+            ParsedStatement::Nexus(PeerDDL::DropAlert { id: 7 })
+        ));
+        assert!(matches!(
+            parse_one("show alerts"),
+            ParsedStatement::Nexus(PeerDDL::ShowAlerts)
+        ));
+    }
+
+    #[test]
+    fn alert_errors() {
+        for sql in [
+            "DROP ALERT 2147483648",
+            "DROP ALERT slack",
+            "CREATE ALERT WITH (config = '{}')",
+            "CREATE ALERT WITH (service = 'pagerduty', config = '{}')",
+            "CREATE ALERT WITH (service = 'email', config = 'not json')",
+            "CREATE ALERT WITH (service = 'email', config = '[]')",
+            "CREATE ALERT WITH (service = 'email', config = '{}', channel = 'ops')",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+        let err = parse_sql(&PostgreSqlDialect {}, "DROP ALERT 2147483648").unwrap_err();
+        assert!(err.to_string().contains("invalid alert id"), "{err}");
+    }
+
+    #[test]
+    fn show_peerdb_settings() {
+        assert!(matches!(
+            parse_one("SHOW PEERDB SETTINGS"),
+            ParsedStatement::Nexus(PeerDDL::ShowSettings)
+        ));
+        // other settings are still shown by sqlparser
+        assert!(matches!(
+            parse_one("SHOW work_mem"),
+            ParsedStatement::Sql(_)
+        ));
+    }
+
+    #[test]
+    fn alter_system_set() {
+        for (sql, expected) in [
+            (
+                "ALTER SYSTEM SET peerdb.cdc_channel_buffer_size = 1024",
+                "1024",
+            ),
+            (
+                "alter system set PeerDB.cdc_channel_buffer_size to '1024'",
+                "1024",
+            ),
+            (
+                "ALTER SYSTEM SET peerdb.cdc_channel_buffer_size = true",
+                "true",
+            ),
+        ] {
+            assert!(
+                matches!(
+                    parse_one(sql),
+                    ParsedStatement::Nexus(PeerDDL::AlterSetting { name, value })
+                        if name == "PEERDB_CDC_CHANNEL_BUFFER_SIZE" && value == expected
+                ),
+                "{sql}"
+            );
+        }
+    }
+
+    #[test]
+    fn alter_system_set_errors() {
+        let err = parse_sql(&PostgreSqlDialect {}, "ALTER SYSTEM SET work_mem = 10").unwrap_err();
+        assert!(err.to_string().contains("only peerdb settings"), "{err}");
+        for sql in [
+            "ALTER SYSTEM SET postgres.work_mem = 10",
+            "ALTER SYSTEM SET peerdb.a.b = 10",
+            "ALTER SYSTEM SET peerdb.a 10",
+            "ALTER SYSTEM SET peerdb.a = some_value",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn create_script() {
+        for (sql, replace) in [
+            ("CREATE SCRIPT MyScript LANGUAGE Lua AS $$return 1$$", false),
+            (
+                "CREATE OR REPLACE SCRIPT MyScript LANGUAGE lua AS 'return 1'",
+                true,
+            ),
+        ] {
+            assert!(
+                matches!(
+                    parse_one(sql),
+                    ParsedStatement::Nexus(PeerDDL::CreateScript {
+                        or_replace,
+                        script_name,
+                        lang,
+                        source,
+                    }) if or_replace == replace
+                        && script_name == "MyScript"
+                        && lang == "lua"
+                        && source == "return 1"
+                ),
+                "{sql}"
+            );
+        }
+    }
+
+    #[test]
+    fn drop_and_show_scripts() {
+        assert!(matches!(
+            parse_one("DROP SCRIPT IF EXISTS MyScript"),
+            ParsedStatement::Nexus(PeerDDL::DropScript {
+                if_exists: true,
+                script_name,
+            }) if script_name == "MyScript"
+        ));
+        assert!(matches!(
+            parse_one("DROP SCRIPT s"),
+            ParsedStatement::Nexus(PeerDDL::DropScript {
+                if_exists: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_one("SHOW SCRIPTS"),
+            ParsedStatement::Nexus(PeerDDL::ShowScripts)
+        ));
+    }
+
+    #[test]
+    fn script_errors() {
+        let err = parse_sql(
+            &PostgreSqlDialect {},
+            "CREATE SCRIPT s LANGUAGE python AS 'print(1)'",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unsupported script language python"),
+            "{err}"
+        );
+        for sql in [
+            "CREATE SCRIPT s AS 'return 1'",
+            "CREATE SCRIPT s LANGUAGE lua",
+            "CREATE SCRIPT s LANGUAGE lua AS 1",
+            "DROP SCRIPT",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn maintenance() {
+        assert!(matches!(
+            parse_one("ENTER MAINTENANCE"),
+            ParsedStatement::Nexus(PeerDDL::EnterMaintenance)
+        ));
+        assert!(matches!(
+            parse_one("exit maintenance"),
+            ParsedStatement::Nexus(PeerDDL::ExitMaintenance)
+        ));
+        assert!(matches!(
+            parse_one("SHOW MAINTENANCE STATUS"),
+            ParsedStatement::Nexus(PeerDDL::ShowMaintenanceStatus)
+        ));
+        assert!(parse_sql(&PostgreSqlDialect {}, "ENTER MAINTENANCE now").is_err());
+    }
+
+    #[test]
+    fn statements_blocked_by_maintenance() {
+        for (sql, changes_mirrors) in [
+            ("ALTER MIRROR m REMOVE TABLE public.a", true),
+            ("ENTER MAINTENANCE", false),
+            ("SHOW MAINTENANCE STATUS", false),
+            ("SHOW MIRRORS", false),
+        ] {
+            let ParsedStatement::Nexus(ddl) = parse_one(sql) else {
+                panic!("{sql} was not parsed as a nexus statement");
+            };
+            assert_eq!(ddl.changes_mirrors(), changes_mirrors, "{sql}");
+        }
+    }
+
+    #[test]
+    fn show_peers() {
+        assert!(matches!(
+            parse_one("SHOW PEERS"),
+            ParsedStatement::Nexus(PeerDDL::ShowPeers)
+        ));
+        assert!(parse_sql(&PostgreSqlDialect {}, "SHOW PEERS pg").is_err());
+    }
+
+    #[test]
+    fn show_mirrors() {
+        assert!(matches!(
+            parse_one("SHOW MIRRORS"),
+            ParsedStatement::Nexus(PeerDDL::ShowMirrors { tags }) if tags.is_empty()
+        ));
+        let ParsedStatement::Nexus(PeerDDL::ShowMirrors { tags }) =
+            parse_one("SHOW MIRRORS WITH TAGS (team = 'data', tier = 1)")
+        else {
+            panic!("SHOW MIRRORS WITH TAGS was not parsed");
+        };
+        assert_eq!(
+            tags,
+            HashMap::from([
+                ("team".to_owned(), "data".to_owned()),
+                ("tier".to_owned(), "1".to_owned()),
+            ])
+        );
+        for sql in [
+            "SHOW MIRRORS WITH TAGS",
+            "SHOW MIRRORS WITH TAGS (team)",
+            "SHOW MIRRORS WITH (team = 'data')",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{sql}");
+        }
+    }
+}
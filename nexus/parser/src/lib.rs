@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, OnceLock},
+};
 
 use analyzer::{
     CursorEvent, PeerCursorAnalyzer, PeerDDL, PeerDDLAnalyzer, PeerExistanceAnalyzer,
@@ -10,7 +14,11 @@ use pgwire::{
     api::{ClientInfo, Type, stmt::QueryParser},
     error::{ErrorInfo, PgWireError, PgWireResult},
 };
-use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
+use sqlparser::{ast::Statement, dialect::PostgreSqlDialect};
+
+mod extension;
+
+pub use extension::ParsedStatement;
 
 const DIALECT: PostgreSqlDialect = PostgreSqlDialect {};
 
@@ -22,7 +30,6 @@ pub struct NexusQueryParser {
 #[derive(Debug, Clone)]
 pub enum NexusStatement {
     PeerDDL {
-        stmt: Statement,
         ddl: Box<PeerDDL>,
     },
     PeerQuery {
@@ -33,7 +40,7 @@ pub enum NexusStatement {
         stmt: Statement,
         cursor: CursorEvent,
     },
-    Rollback {
+    Transaction {
         stmt: Statement,
     },
     Empty,
@@ -41,9 +48,20 @@ pub enum NexusStatement {
 
 impl NexusStatement {
     pub fn new(
-        peers: HashMap<String, pt::peerdb_peers::Peer>,
+        peers: &HashMap<String, pt::peerdb_peers::Peer>,
         stmt: &Statement,
     ) -> PgWireResult<Self> {
+        if matches!(
+            stmt,
+            Statement::StartTransaction { .. }
+                | Statement::Commit { .. }
+                | Statement::Rollback { .. }
+                | Statement::Savepoint { .. }
+                | Statement::ReleaseSavepoint { .. }
+        ) {
+            return Ok(NexusStatement::Transaction { stmt: stmt.clone() });
+        }
+
         let ddl = PeerDDLAnalyzer.analyze(stmt).map_err(|e| {
             PgWireError::UserError(Box::new(ErrorInfo::new(
                 "ERROR".to_owned(),
@@ -53,10 +71,7 @@ impl NexusStatement {
         })?;
 
         if let Some(ddl) = ddl {
-            return Ok(NexusStatement::PeerDDL {
-                stmt: stmt.clone(),
-                ddl: Box::new(ddl),
-            });
+            return Ok(NexusStatement::PeerDDL { ddl: Box::new(ddl) });
         }
 
         if let Ok(Some(cursor)) = PeerCursorAnalyzer.analyze(stmt) {
@@ -67,7 +82,7 @@ impl NexusStatement {
         }
 
         let assoc = {
-            let pea = PeerExistanceAnalyzer::new(&peers);
+            let pea = PeerExistanceAnalyzer::new(peers);
             pea.analyze(stmt).map_err(|e| {
                 PgWireError::UserError(Box::new(ErrorInfo::new(
                     "ERROR".to_owned(),
@@ -84,10 +99,42 @@ impl NexusStatement {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct NexusParsedStatement {
     pub statement: NexusStatement,
     pub query: String,
+    /// Parameter types inferred by the peer when the statement was described, the
+    /// portals bound to the statement decode their binary parameters with them.
+    pub inferred_parameter_types: Arc<OnceLock<Vec<Type>>>,
+}
+
+impl NexusParsedStatement {
+    /// The query as it may be logged, user statements carry a password and are not.
+    pub fn loggable_query(&self) -> &str {
+        match &self.statement {
+            NexusStatement::PeerDDL { ddl } if ddl.manages_users() => "<user statement>",
+            _ => &self.query,
+        }
+    }
+}
+
+impl fmt::Debug for NexusParsedStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NexusParsedStatement")
+            .field("statement", &self.statement)
+            .field("query", &self.loggable_query())
+            .field("inferred_parameter_types", &self.inferred_parameter_types)
+            .finish()
+    }
+}
+
+// the statements are only shown through Debug, which leaves out passwords
+fn multiple_statements_error(stmts: &[ParsedStatement]) -> PgWireError {
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_owned(),
+        "42P14".to_owned(),
+        format!("only one statement can be prepared at a time, got {stmts:?}"),
+    )))
 }
 
 impl NexusQueryParser {
@@ -95,8 +142,10 @@ impl NexusQueryParser {
         Self { catalog }
     }
 
-    pub async fn get_peers_bridge(&self) -> PgWireResult<HashMap<String, pt::peerdb_peers::Peer>> {
-        let peers = self.catalog.get_peers().await;
+    pub async fn get_peers_bridge(
+        &self,
+    ) -> PgWireResult<Arc<HashMap<String, pt::peerdb_peers::Peer>>> {
+        let peers = self.catalog.get_cached_peers().await;
 
         peers.map_err(|e| {
             PgWireError::UserError(Box::new(ErrorInfo::new(
@@ -107,36 +156,32 @@ impl NexusQueryParser {
         })
     }
 
-    pub async fn parse_simple_sql(&self, sql: &str) -> PgWireResult<NexusParsedStatement> {
-        let mut stmts =
-            Parser::parse_sql(&DIALECT, sql).map_err(|e| PgWireError::ApiError(Box::new(e)))?;
-        if stmts.len() > 1 {
-            let err_msg = format!("unsupported sql: {sql}, statements: {stmts:?}");
-            // TODO (kaushik): Better error message for this. When do we start seeing multiple statements?
-            Err(PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "42P14".to_owned(),
-                err_msg,
-            ))))
-        } else if stmts.is_empty() {
-            Ok(NexusParsedStatement {
-                statement: NexusStatement::Empty,
-                query: sql.to_owned(),
-            })
-        } else {
-            let stmt = stmts.remove(0);
-            if matches!(stmt, Statement::Rollback { .. }) {
-                Ok(NexusParsedStatement {
-                    statement: NexusStatement::Rollback { stmt },
-                    query: sql.to_owned(),
-                })
-            } else {
+    /// Split a simple query into its statements. Peers are resolved per statement by
+    /// [`Self::analyze_simple_statement`] right before it runs, so that a script can use
+    /// a peer created by one of its earlier statements.
+    pub fn split_simple_sql(&self, sql: &str) -> PgWireResult<Vec<ParsedStatement>> {
+        extension::parse_sql(&DIALECT, sql).map_err(|e| PgWireError::ApiError(Box::new(e)))
+    }
+
+    pub async fn analyze_simple_statement(
+        &self,
+        stmt: ParsedStatement,
+    ) -> PgWireResult<NexusStatement> {
+        match stmt {
+            ParsedStatement::Nexus(ddl) => Ok(NexusStatement::PeerDDL { ddl: Box::new(ddl) }),
+            ParsedStatement::Sql(stmt) => {
+                let peers = self.get_peers_bridge().await?;
+                NexusStatement::new(&peers, &stmt)
+            }
+            ParsedStatement::CreateOrReplacePeer(stmt) => {
                 let peers = self.get_peers_bridge().await?;
-                let nexus_stmt = NexusStatement::new(peers, &stmt)?;
-                Ok(NexusParsedStatement {
-                    statement: nexus_stmt,
-                    query: sql.to_owned(),
-                })
+                let mut statement = NexusStatement::new(&peers, &stmt)?;
+                if let NexusStatement::PeerDDL { ddl } = &mut statement {
+                    if let PeerDDL::CreatePeer { or_replace, .. } = ddl.as_mut() {
+                        *or_replace = true;
+                    }
+                }
+                Ok(statement)
             }
         }
     }
@@ -156,27 +201,65 @@ impl QueryParser for NexusQueryParser {
         C: ClientInfo + Unpin + Send + Sync,
     {
         let mut stmts =
-            Parser::parse_sql(&DIALECT, sql).map_err(|e| PgWireError::ApiError(Box::new(e)))?;
+            extension::parse_sql(&DIALECT, sql).map_err(|e| PgWireError::ApiError(Box::new(e)))?;
         if stmts.len() > 1 {
-            let err_msg = format!("unsupported sql: {sql}, statements: {stmts:?}");
-            Err(PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "42P14".to_owned(),
-                err_msg,
-            ))))
+            Err(multiple_statements_error(&stmts))
         } else if stmts.is_empty() {
             Ok(NexusParsedStatement {
                 statement: NexusStatement::Empty,
                 query: sql.to_owned(),
+                inferred_parameter_types: Default::default(),
             })
         } else {
-            let stmt = stmts.remove(0);
-            let peers = self.get_peers_bridge().await?;
-            let nexus_stmt = NexusStatement::new(peers, &stmt)?;
+            let statement = self.analyze_simple_statement(stmts.remove(0)).await?;
             Ok(NexusParsedStatement {
-                statement: nexus_stmt,
+                statement,
                 query: sql.to_owned(),
+                inferred_parameter_types: Default::default(),
             })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQL: &str = "CREATE USER alice PASSWORD 'hunter2'; ALTER USER bob PASSWORD 'hunter3'";
+
+    #[test]
+    fn passwords_are_not_in_errors() {
+        let stmts = extension::parse_sql(&DIALECT, SQL).unwrap();
+        let PgWireError::UserError(err) = multiple_statements_error(&stmts) else {
+            panic!("expected a user error");
+        };
+        assert!(err.message.contains("alice"), "{}", err.message);
+        assert!(!err.message.contains("hunter"), "{}", err.message);
+    }
+
+    #[test]
+    fn user_statements_are_not_logged() {
+        for (sql, stmt) in SQL
+            .split(';')
+            .zip(extension::parse_sql(&DIALECT, SQL).unwrap())
+        {
+            let ParsedStatement::Nexus(ddl) = stmt else {
+                panic!("{sql} was not parsed as a nexus statement");
+            };
+            let stmt = NexusParsedStatement {
+                statement: NexusStatement::PeerDDL { ddl: Box::new(ddl) },
+                query: sql.to_owned(),
+                inferred_parameter_types: Default::default(),
+            };
+            assert!(!stmt.loggable_query().contains("hunter"));
+            assert!(!format!("{stmt:?}").contains("hunter"));
+        }
+
+        let stmt = NexusParsedStatement {
+            statement: NexusStatement::Empty,
+            query: "SELECT 1".to_owned(),
+            inferred_parameter_types: Default::default(),
+        };
+        assert_eq!(stmt.loggable_query(), "SELECT 1");
+    }
+}
@@ -1,4 +1,53 @@
-use sqlparser::ast::{Array, ArrayElemTypeDef, DataType, Expr};
+use std::ops::ControlFlow;
+
+use sqlparser::ast::{
+    Array, ArrayElemTypeDef, DataType, Expr, Visit, VisitMut, visit_expressions,
+    visit_expressions_mut,
+};
+
+fn placeholder_index(placeholder: &str) -> Option<usize> {
+    placeholder
+        .strip_prefix('$')
+        .and_then(|idx| idx.parse::<usize>().ok())
+        .filter(|idx| *idx > 0)
+}
+
+/// Rewrite postgres style `$n` placeholders for peers with a different bind syntax,
+/// `rewrite` receives the 1-based index of the parameter.
+pub fn rewrite_placeholders<V: VisitMut>(
+    node: &mut V,
+    rewrite: impl Fn(usize) -> String,
+) -> anyhow::Result<()> {
+    let res = visit_expressions_mut(node, |expr| {
+        if let Expr::Value(sqlparser::ast::Value::Placeholder(placeholder)) = expr {
+            match placeholder_index(placeholder) {
+                Some(idx) => *placeholder = rewrite(idx),
+                None => return ControlFlow::Break(placeholder.clone()),
+            }
+        }
+        ControlFlow::Continue(())
+    });
+
+    if let ControlFlow::Break(placeholder) = res {
+        Err(anyhow::anyhow!("unsupported placeholder: {}", placeholder))
+    } else {
+        Ok(())
+    }
+}
+
+/// Number of parameters referenced through `$n` placeholders, the highest `n`.
+pub fn placeholder_count<V: Visit>(node: &V) -> usize {
+    let mut count = 0;
+    let _ = visit_expressions(node, |expr| {
+        if let Expr::Value(sqlparser::ast::Value::Placeholder(placeholder)) = expr {
+            if let Some(idx) = placeholder_index(placeholder) {
+                count = count.max(idx);
+            }
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    count
+}
 
 /// Flatten Cast EXPR to List with right value type
 /// For example Value(SingleQuotedString("{hash1,hash2}") must return
@@ -99,3 +148,60 @@ fn pour_array_into_list(arr: &Array, mut list: Vec<Expr>) -> anyhow::Result<Vec<
     }
     Ok(list)
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
+
+    use super::*;
+
+    fn parse(sql: &str) -> Statement {
+        Parser::parse_sql(&PostgreSqlDialect {}, sql)
+            .unwrap()
+            .remove(0)
+    }
+
+    fn rewrite(sql: &str) -> anyhow::Result<String> {
+        let mut stmt = parse(sql);
+        rewrite_placeholders(&mut stmt, |idx| format!("@p{idx}"))?;
+        Ok(stmt.to_string())
+    }
+
+    #[test]
+    fn placeholder_count_is_the_highest_index() {
+        assert_eq!(placeholder_count(&parse("SELECT 1")), 0);
+        assert_eq!(placeholder_count(&parse("SELECT $1, $10")), 10);
+        assert_eq!(placeholder_count(&parse("SELECT $2 WHERE a = $2")), 2);
+        assert_eq!(placeholder_count(&parse("SELECT $3 WHERE a = $1")), 3);
+    }
+
+    #[test]
+    fn rewrite_two_digit_placeholders() {
+        assert_eq!(
+            rewrite("SELECT $1, $10 FROM t WHERE a = $2").unwrap(),
+            "SELECT @p1, @p10 FROM t WHERE a = @p2"
+        );
+    }
+
+    #[test]
+    fn rewrite_reused_placeholders() {
+        assert_eq!(
+            rewrite("SELECT $1 FROM t WHERE a = $1 OR b = $1").unwrap(),
+            "SELECT @p1 FROM t WHERE a = @p1 OR b = @p1"
+        );
+    }
+
+    #[test]
+    fn rewrite_out_of_order_placeholders() {
+        assert_eq!(
+            rewrite("SELECT $3, $1 FROM t WHERE a IN ($2)").unwrap(),
+            "SELECT @p3, @p1 FROM t WHERE a IN (@p2)"
+        );
+    }
+
+    #[test]
+    fn rewrite_rejects_unnumbered_placeholders() {
+        let err = rewrite("SELECT $0").unwrap_err();
+        assert_eq!(err.to_string(), "unsupported placeholder: $0");
+    }
+}
@@ -1,9 +1,19 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use anyhow::Context;
 use gcp_bigquery_client::{
     Client,
-    model::{query_request::QueryRequest, query_response::QueryResponse},
+    model::{
+        get_query_results_parameters::GetQueryResultsParameters, job_reference::JobReference,
+        query_parameter::QueryParameter, query_request::QueryRequest,
+        query_response::QueryResponse,
+    },
     yup_oauth2,
 };
 use peer_connections::PeerConnectionTracker;
@@ -14,8 +24,12 @@ use sqlparser::ast::{CloseCursor, Declare, Expr, FetchDirection, Statement, Valu
 use stream::{BqRecordStream, BqSchema};
 
 mod ast;
+mod params;
 mod stream;
 
+// how long a single request waits for the query job to complete
+const QUERY_POLL_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct BigQueryQueryExecutor {
     peer_name: String,
     project_id: String,
@@ -23,6 +37,8 @@ pub struct BigQueryQueryExecutor {
     peer_connections: PeerConnectionTracker,
     client: Box<Client>,
     cursor_manager: CursorManager,
+    // cancel flag of the running query, checked each time its job is polled
+    running_query: Mutex<Option<Arc<AtomicBool>>>,
 }
 
 pub async fn bq_client_from_config(config: &BigqueryConfig) -> anyhow::Result<Client> {
@@ -60,12 +76,21 @@ impl BigQueryQueryExecutor {
             peer_connections,
             client: Box::new(client),
             cursor_manager: Default::default(),
+            running_query: Mutex::new(None),
         })
     }
 
-    async fn run_tracked(&self, query: &str) -> PgWireResult<QueryResponse> {
+    async fn run_tracked(
+        &self,
+        query: &str,
+        query_parameters: Option<Vec<QueryParameter>>,
+    ) -> PgWireResult<QueryResponse> {
         let mut query_req = QueryRequest::new(query);
-        query_req.timeout_ms = Some(Duration::from_secs(120).as_millis() as i32);
+        query_req.timeout_ms = Some(QUERY_POLL_TIMEOUT.as_millis() as i32);
+        if let Some(query_parameters) = query_parameters {
+            query_req.parameter_mode = Some("NAMED".to_owned());
+            query_req.query_parameters = Some(query_parameters);
+        }
 
         let token = self
             .peer_connections
@@ -76,24 +101,97 @@ impl BigQueryQueryExecutor {
                 PgWireError::ApiError(err.into())
             })?;
 
-        let result_set = self.client.job().query(&self.project_id, query_req).await;
+        let result_set = self.run_query(query_req).await;
 
         token.end().await.map_err(|err| {
             tracing::error!("error closing tracking token: {}", err);
             PgWireError::ApiError(err.into())
         })?;
 
-        result_set.map_err(|err| {
-            tracing::error!("error running query: {}", err);
-            PgWireError::ApiError(err.into())
-        })
+        result_set
+    }
+
+    // a cancel request only applies to the query running when it arrives
+    async fn run_query(&self, query_req: QueryRequest) -> PgWireResult<QueryResponse> {
+        let cancel_requested = Arc::new(AtomicBool::new(false));
+        *self.running_query.lock().unwrap() = Some(cancel_requested.clone());
+        let res = self.poll_query(query_req, &cancel_requested).await;
+        self.running_query.lock().unwrap().take();
+        res
+    }
+
+    // a query outlasting the timeout keeps running as a job, which is polled
+    // until it completes or the client cancels it.
+    async fn poll_query(
+        &self,
+        query_req: QueryRequest,
+        cancel_requested: &AtomicBool,
+    ) -> PgWireResult<QueryResponse> {
+        let mut query_response = self
+            .client
+            .job()
+            .query(&self.project_id, query_req)
+            .await
+            .map_err(|err| {
+                tracing::error!("error running query: {}", err);
+                PgWireError::ApiError(err.into())
+            })?;
+
+        while query_response.job_complete == Some(false) {
+            let Some(JobReference {
+                job_id: Some(job_id),
+                location,
+                ..
+            }) = &query_response.job_reference
+            else {
+                break;
+            };
+
+            if cancel_requested.load(Ordering::Relaxed) {
+                tracing::info!("cancelling bigquery job {}", job_id);
+                self.client
+                    .job()
+                    .cancel_job(&self.project_id, job_id, location.as_deref())
+                    .await
+                    .map_err(|err| {
+                        tracing::error!("error cancelling job: {}", err);
+                        PgWireError::ApiError(err.into())
+                    })?;
+                return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "57014".to_owned(),
+                    "canceling statement due to user request".to_owned(),
+                ))));
+            }
+
+            let query_results = self
+                .client
+                .job()
+                .get_query_results(
+                    &self.project_id,
+                    job_id,
+                    GetQueryResultsParameters {
+                        location: location.clone(),
+                        timeout_ms: Some(QUERY_POLL_TIMEOUT.as_millis() as i32),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|err| {
+                    tracing::error!("error polling query job: {}", err);
+                    PgWireError::ApiError(err.into())
+                })?;
+            query_response = QueryResponse::from(query_results);
+        }
+
+        Ok(query_response)
     }
 }
 
 #[async_trait::async_trait]
 impl QueryExecutor for BigQueryQueryExecutor {
     async fn execute_raw(&self, query: &str) -> PgWireResult<QueryOutput> {
-        let query_response = self.run_tracked(query).await?;
+        let query_response = self.run_tracked(query, None).await?;
         let cursor = BqRecordStream::from(query_response);
         tracing::info!(
             "retrieved {} rows for query {}",
@@ -204,6 +302,49 @@ impl QueryExecutor for BigQueryQueryExecutor {
         }
     }
 
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[value::Value],
+    ) -> PgWireResult<QueryOutput> {
+        match stmt {
+            Statement::Query(query) => {
+                let mut query = query.clone();
+                ast::BigqueryAst
+                    .rewrite(&self.peer_name, &self.dataset_id, &mut query)
+                    .context("unable to rewrite query")
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+                peer_ast::rewrite_placeholders(&mut query, params::placeholder)
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+
+                let query = query.to_string();
+                tracing::info!("bq rewritten query: {}", query);
+
+                let query_response = self
+                    .run_tracked(&query, Some(params::to_query_parameters(params)))
+                    .await?;
+                let cursor = BqRecordStream::from(query_response);
+                tracing::info!(
+                    "retrieved {} rows for query {}",
+                    cursor.get_num_records(),
+                    query
+                );
+                Ok(QueryOutput::Stream(Box::pin(cursor)))
+            }
+            _ if params.is_empty() => self.execute(stmt).await,
+            _ => {
+                let error = format!(
+                    "only SELECT statements support parameters in bigquery. got: {stmt}"
+                );
+                PgWireResult::Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "fdw_error".to_owned(),
+                    error,
+                ))))
+            }
+        }
+    }
+
     // describe the output of the query
     async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
         // print the statement
@@ -223,7 +364,7 @@ impl QueryExecutor for BigQueryQueryExecutor {
                 query.limit = Some(Expr::Value(Value::Number("0".to_owned(), false)));
 
                 let query = query.to_string();
-                let query_response = self.run_tracked(&query).await?;
+                let query_response = self.run_tracked(&query, None).await?;
                 let schema = BqSchema::from(&query_response);
 
                 // log the schema
@@ -256,4 +397,16 @@ impl QueryExecutor for BigQueryQueryExecutor {
             )))),
         }
     }
+
+    async fn cancel(&self) -> PgWireResult<()> {
+        if let Some(cancel_requested) = self.running_query.lock().unwrap().as_ref() {
+            cancel_requested.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    async fn reset(&self) -> PgWireResult<()> {
+        self.cursor_manager.close_all_cursors().await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,113 @@
+use base64::prelude::{BASE64_STANDARD, Engine as _};
+use gcp_bigquery_client::model::{
+    query_parameter::QueryParameter, query_parameter_type::QueryParameterType,
+    query_parameter_value::QueryParameterValue,
+};
+use value::Value;
+
+// `$n` is bound as the named parameter `@pn`
+pub fn placeholder(idx: usize) -> String {
+    format!("@p{idx}")
+}
+
+pub fn to_query_parameters(params: &[Value]) -> Vec<QueryParameter> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            let (r#type, value) = to_typed_value(value);
+            QueryParameter {
+                name: Some(format!("p{}", idx + 1)),
+                parameter_type: Some(QueryParameterType {
+                    r#type: r#type.to_owned(),
+                    ..Default::default()
+                }),
+                parameter_value: Some(QueryParameterValue {
+                    value,
+                    ..Default::default()
+                }),
+            }
+        })
+        .collect()
+}
+
+fn to_typed_value(value: &Value) -> (&'static str, Option<String>) {
+    match value {
+        Value::Null => ("STRING", None),
+        Value::Bool(v) => ("BOOL", Some(v.to_string())),
+        Value::TinyInt(v) => ("INT64", Some(v.to_string())),
+        Value::SmallInt(v) => ("INT64", Some(v.to_string())),
+        Value::Oid(v) => ("INT64", Some(v.to_string())),
+        Value::Integer(v) => ("INT64", Some(v.to_string())),
+        Value::BigInt(v) => ("INT64", Some(v.to_string())),
+        Value::Float(v) => ("FLOAT64", Some(v.to_string())),
+        Value::Double(v) => ("FLOAT64", Some(v.to_string())),
+        Value::Numeric(v) => ("BIGNUMERIC", Some(v.to_string())),
+        Value::Binary(b) | Value::VarBinary(b) => ("BYTES", Some(BASE64_STANDARD.encode(b))),
+        Value::Date(v) => ("DATE", Some(v.to_string())),
+        Value::Time(v) | Value::TimeWithTimeZone(v) => ("TIME", Some(v.to_string())),
+        Value::PostgresTimestamp(v) => ("DATETIME", Some(v.to_string())),
+        Value::Timestamp(v) | Value::TimestampWithTimeZone(v) => {
+            ("TIMESTAMP", Some(v.to_rfc3339()))
+        }
+        Value::Json(v) | Value::JsonB(v) => ("JSON", Some(v.to_string())),
+        Value::Char(v) => ("STRING", Some(v.to_string())),
+        Value::VarChar(s) | Value::Text(s) | Value::Enum(s) => ("STRING", Some(s.clone())),
+        // bigquery has no interval parameters, the query casts the text if it needs to
+        Value::Interval(v) => ("STRING", Some(v.to_string())),
+        Value::Uuid(v) => ("STRING", Some(v.to_string())),
+        Value::IpAddr(v) => ("STRING", Some(v.to_string())),
+        Value::Array(_) | Value::Hstore(_) => {
+            ("JSON", Some(value.to_serde_json_value().to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+    use super::*;
+
+    #[test]
+    fn placeholders_are_named() {
+        let mut stmt = Parser::parse_sql(&PostgreSqlDialect {}, "SELECT $2, $10 WHERE a = $2")
+            .unwrap()
+            .remove(0);
+        peer_ast::rewrite_placeholders(&mut stmt, placeholder).unwrap();
+        assert_eq!(stmt.to_string(), "SELECT @p2, @p10 WHERE a = @p2");
+    }
+
+    #[test]
+    fn parameters_are_named_by_position() {
+        let params = (1..=10).map(Value::BigInt).collect::<Vec<_>>();
+        let params = to_query_parameters(&params);
+        let names = params
+            .iter()
+            .map(|param| param.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["p1", "p2", "p3", "p4", "p5", "p6", "p7", "p8", "p9", "p10"]
+        );
+        let value = params[9].parameter_value.as_ref().unwrap();
+        assert_eq!(value.value.as_deref(), Some("10"));
+    }
+
+    #[test]
+    fn typed_values() {
+        assert_eq!(to_typed_value(&Value::Null), ("STRING", None));
+        assert_eq!(
+            to_typed_value(&Value::Bool(true)),
+            ("BOOL", Some("true".to_owned()))
+        );
+        assert_eq!(
+            to_typed_value(&Value::Binary(b"hi".to_vec().into())),
+            ("BYTES", Some("aGk=".to_owned()))
+        );
+        assert_eq!(
+            to_typed_value(&Value::Text("a".to_owned())),
+            ("STRING", Some("a".to_owned()))
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use std::{pin::Pin, sync::Arc};
 
 use futures::Stream;
-use pgwire::{api::results::FieldInfo, error::PgWireResult};
+use pgwire::{
+    api::{Type, results::FieldInfo},
+    error::PgWireResult,
+};
 use sqlparser::ast::Statement;
 use value::Value;
 
@@ -48,7 +51,30 @@ pub enum QueryOutput {
 pub trait QueryExecutor: Send + Sync {
     async fn execute_raw(&self, stmt: &str) -> PgWireResult<QueryOutput>;
     async fn execute(&self, stmt: &Statement) -> PgWireResult<QueryOutput>;
+    /// Execute a statement containing `$n` placeholders, `params[n - 1]` is bound
+    /// to `$n` using the peer's native parameter support.
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[Value],
+    ) -> PgWireResult<QueryOutput>;
     async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>>;
+    /// Types of the `$n` placeholders of a statement, if the peer is able to infer them.
+    async fn describe_params(&self, _stmt: &Statement) -> PgWireResult<Option<Vec<Type>>> {
+        Ok(None)
+    }
+    /// Ask the peer to cancel the query this executor is running, if there is one.
+    async fn cancel(&self) -> PgWireResult<()>;
+    /// Clear what a session left behind on the connection, such as settings, temp tables
+    /// and cursors, before the executor is handed to another session.
+    async fn reset(&self) -> PgWireResult<()> {
+        Ok(())
+    }
+    /// Whether the connection of the executor is gone for good, closed executors are
+    /// replaced by a new one.
+    fn is_closed(&self) -> bool {
+        false
+    }
 }
 
 pub struct Cursor {
@@ -1,4 +1,4 @@
-use futures::{StreamExt, stream};
+use futures::{StreamExt, TryStreamExt, stream};
 use pgwire::{
     api::results::{DataRowEncoder, QueryResponse, Response},
     error::{PgWireError, PgWireResult},
@@ -94,3 +94,11 @@ pub fn records_to_query_response<'a>(records: Records) -> PgWireResult<Response<
         data_row_stream,
     )))
 }
+
+/// Read every record of a stream up front, for when the peer connection has
+/// to serve another statement before the client gets to consume the rows.
+pub async fn collect_records(record_stream: SendableStream) -> PgWireResult<Records> {
+    let schema = record_stream.schema();
+    let records = record_stream.try_collect().await?;
+    Ok(Records { records, schema })
+}
@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
-use futures::StreamExt;
-use mysql_async::{self, prelude::Queryable};
-use tokio::{spawn, sync::mpsc};
+use futures::{Stream, StreamExt};
+use mysql_async::{self, Params, prelude::Queryable};
+use tokio::{
+    spawn,
+    sync::{mpsc, oneshot},
+};
 
 pub enum Response {
     Row(mysql_async::Row),
@@ -12,42 +15,111 @@ pub enum Response {
 
 pub struct Message {
     pub query: String,
+    /// Bound through a prepared statement unless empty
+    pub params: Params,
     pub response: mpsc::Sender<Response>,
 }
 
+pub enum Request {
+    Query(Message),
+    /// Clear the session state of the connection, like a newly opened one
+    Reset(oneshot::Sender<mysql_async::Result<()>>),
+}
+
 #[derive(Clone)]
 pub struct MyClient {
-    pub chan: mpsc::Sender<Message>,
+    pub chan: mpsc::Sender<Request>,
+    opts: mysql_async::Opts,
+    connection_id: u32,
 }
 
 impl MyClient {
     pub async fn new(opts: mysql_async::Opts) -> mysql_async::Result<MyClient> {
-        let mut conn = mysql_async::Conn::new(opts).await?;
+        let mut conn = mysql_async::Conn::new(opts.clone()).await?;
+        let connection_id = conn.id();
         let (send, mut recv) = mpsc::channel(1);
         spawn(async move {
-            while let Some(Message { query, response }) = recv.recv().await {
-                match conn.query_stream(query).await {
-                    Ok(stream) => {
-                        response.send(Response::Schema(stream.columns())).await.ok();
-                        stream
-                            .for_each_concurrent(1, async |row| {
-                                response
-                                    .send(match row {
-                                        Ok(row) => Response::Row(row),
-                                        Err(err) => Response::Err(err),
-                                    })
-                                    .await
-                                    .ok();
-                            })
-                            .await;
+            while let Some(request) = recv.recv().await {
+                let Message {
+                    query,
+                    params,
+                    response,
+                } = match request {
+                    Request::Query(message) => message,
+                    Request::Reset(done) => {
+                        done.send(conn.reset().await.map(|_| ())).ok();
+                        continue;
+                    }
+                };
+                let res = if let Params::Empty = params {
+                    match conn.query_stream(query).await {
+                        Ok(stream) => Ok(send_rows(stream.columns(), stream, &response).await),
+                        Err(e) => Err(e),
                     }
-                    Err(e) => {
-                        response.send(Response::Err(e)).await.ok();
+                } else {
+                    match conn.exec_stream(query, params).await {
+                        Ok(stream) => Ok(send_rows(stream.columns(), stream, &response).await),
+                        Err(e) => Err(e),
+                    }
+                };
+                if let Err(e) = res {
+                    // the connection is gone, closing the channel marks the client as closed
+                    let closed = matches!(e, mysql_async::Error::Io(_));
+                    response.send(Response::Err(e)).await.ok();
+                    if closed {
+                        break;
                     }
                 }
             }
         });
 
-        Ok(MyClient { chan: send })
+        Ok(MyClient {
+            chan: send,
+            opts,
+            connection_id,
+        })
+    }
+
+    // the connection is busy running the query, so KILL QUERY is sent over a new one
+    pub async fn cancel(&self) -> mysql_async::Result<()> {
+        let mut conn = mysql_async::Conn::new(self.opts.clone()).await?;
+        let res = conn
+            .query_drop(format!("KILL QUERY {}", self.connection_id))
+            .await;
+        conn.disconnect().await?;
+        res
     }
+
+    pub async fn reset(&self) -> mysql_async::Result<()> {
+        let (done, reset) = oneshot::channel();
+        if self.chan.send(Request::Reset(done)).await.is_err() {
+            return Err(mysql_async::DriverError::ConnectionClosed.into());
+        }
+        reset
+            .await
+            .unwrap_or_else(|_| Err(mysql_async::DriverError::ConnectionClosed.into()))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+
+async fn send_rows(
+    columns: Arc<[mysql_async::Column]>,
+    stream: impl Stream<Item = mysql_async::Result<mysql_async::Row>>,
+    response: &mpsc::Sender<Response>,
+) {
+    response.send(Response::Schema(columns)).await.ok();
+    stream
+        .for_each_concurrent(1, async |row| {
+            response
+                .send(match row {
+                    Ok(row) => Response::Row(row),
+                    Err(err) => Response::Err(err),
+                })
+                .await
+                .ok();
+        })
+        .await;
 }
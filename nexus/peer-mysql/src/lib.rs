@@ -1,5 +1,6 @@
 mod ast;
 mod client;
+mod params;
 mod stream;
 
 use std::fmt::Write;
@@ -48,11 +49,19 @@ impl MySqlQueryExecutor {
     }
 
     async fn query(&self, query: String) -> PgWireResult<MyRecordStream> {
-        MyRecordStream::query(self.client.clone(), query).await
+        MyRecordStream::query(self.client.clone(), query, mysql_async::Params::Empty).await
+    }
+
+    async fn query_with_params(
+        &self,
+        query: String,
+        params: mysql_async::Params,
+    ) -> PgWireResult<MyRecordStream> {
+        MyRecordStream::query(self.client.clone(), query, params).await
     }
 
     async fn query_schema(&self, query: String) -> PgWireResult<Schema> {
-        let stream = MyRecordStream::query(self.client.clone(), query).await?;
+        let stream = self.query(query).await?;
         Ok(stream.schema())
     }
 }
@@ -195,6 +204,39 @@ impl QueryExecutor for MySqlQueryExecutor {
         }
     }
 
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[value::Value],
+    ) -> PgWireResult<QueryOutput> {
+        match stmt {
+            Statement::Query(query) => {
+                let mut query = query.clone();
+                ast::rewrite_query(&self.peer_name, &mut query);
+                peer_ast::rewrite_placeholders(&mut query, params::placeholder)
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+                let query = query.to_string();
+                tracing::info!("mysql rewritten query: {}", query);
+
+                let cursor = self
+                    .query_with_params(query, params::to_mysql_params(params))
+                    .await?;
+                Ok(QueryOutput::Stream(Box::pin(cursor)))
+            }
+            _ if params.is_empty() => self.execute(stmt).await,
+            _ => {
+                let error = format!(
+                    "only SELECT statements support parameters in mysql. got: {stmt}"
+                );
+                Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "fdw_error".to_owned(),
+                    error,
+                ))))
+            }
+        }
+    }
+
     // describe the output of the query
     async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
         // print the statement
@@ -232,4 +274,23 @@ impl QueryExecutor for MySqlQueryExecutor {
             )))),
         }
     }
+
+    async fn cancel(&self) -> PgWireResult<()> {
+        self.client.cancel().await.map_err(|err| {
+            tracing::error!("error cancelling query: {}", err);
+            PgWireError::ApiError(err.into())
+        })
+    }
+
+    async fn reset(&self) -> PgWireResult<()> {
+        self.cursor_manager.close_all_cursors().await?;
+        self.client.reset().await.map_err(|err| {
+            tracing::error!("error resetting connection: {}", err);
+            PgWireError::ApiError(err.into())
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.client.is_closed()
+    }
 }
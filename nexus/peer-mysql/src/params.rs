@@ -0,0 +1,104 @@
+use mysql_async::{Params, Value as MyValue};
+use value::Value;
+
+// `$n` is bound as the named parameter `:pn`, which mysql_async maps onto the
+// positional parameters of the prepared statement, so a parameter may be
+// referenced more than once.
+pub fn placeholder(idx: usize) -> String {
+    format!(":p{idx}")
+}
+
+pub fn to_mysql_params(params: &[Value]) -> Params {
+    if params.is_empty() {
+        return Params::Empty;
+    }
+    Params::from(
+        params
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (format!("p{}", idx + 1), to_mysql_value(value)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn to_mysql_value(value: &Value) -> MyValue {
+    match value {
+        Value::Null => MyValue::NULL,
+        Value::Bool(v) => MyValue::from(*v),
+        Value::TinyInt(v) => MyValue::from(*v),
+        Value::SmallInt(v) => MyValue::from(*v),
+        Value::Oid(v) => MyValue::from(*v),
+        Value::Integer(v) => MyValue::from(*v),
+        Value::BigInt(v) => MyValue::from(*v),
+        Value::Float(v) => MyValue::from(*v),
+        Value::Double(v) => MyValue::from(*v),
+        Value::Numeric(v) => MyValue::from(*v),
+        Value::Char(v) => MyValue::from(v.to_string()),
+        Value::VarChar(s) | Value::Text(s) | Value::Enum(s) => MyValue::from(s.as_str()),
+        Value::Binary(b) | Value::VarBinary(b) => MyValue::from(b.to_vec()),
+        Value::Date(v) => MyValue::from(*v),
+        Value::Time(v) | Value::TimeWithTimeZone(v) => MyValue::from(*v),
+        Value::PostgresTimestamp(v) => MyValue::from(*v),
+        Value::Timestamp(v) | Value::TimestampWithTimeZone(v) => MyValue::from(v.naive_utc()),
+        Value::Uuid(v) => MyValue::from(v.to_string()),
+        Value::IpAddr(v) => MyValue::from(v.to_string()),
+        Value::Interval(v) => MyValue::from(*v),
+        Value::Json(_) | Value::JsonB(_) | Value::Array(_) | Value::Hstore(_) => {
+            MyValue::from(value.to_serde_json_value().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::{dialect::PostgreSqlDialect, parser::Parser};
+
+    use super::*;
+
+    fn rewrite(sql: &str) -> String {
+        let mut stmt = Parser::parse_sql(&PostgreSqlDialect {}, sql)
+            .unwrap()
+            .remove(0);
+        peer_ast::rewrite_placeholders(&mut stmt, placeholder).unwrap();
+        stmt.to_string()
+    }
+
+    #[test]
+    fn placeholders_are_named() {
+        assert_eq!(
+            rewrite("SELECT $1, $10 FROM t WHERE a = $2 OR b = $1"),
+            "SELECT :p1, :p10 FROM t WHERE a = :p2 OR b = :p1"
+        );
+    }
+
+    #[test]
+    fn params_follow_their_names() {
+        let values = (1..=10).map(Value::Integer).collect::<Vec<_>>();
+        // the names in the order the rewritten query refers to them
+        let names = ["p10", "p1", "p2", "p1"].map(|name| name.as_bytes().to_vec());
+        let Params::Positional(positional) =
+            to_mysql_params(&values).into_positional(&names).unwrap()
+        else {
+            panic!("expected positional params");
+        };
+        assert_eq!(positional, [10i32, 1, 2, 1].map(MyValue::from).to_vec());
+    }
+
+    #[test]
+    fn no_params() {
+        assert_eq!(to_mysql_params(&[]), Params::Empty);
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(to_mysql_value(&Value::Null), MyValue::NULL);
+        assert_eq!(
+            to_mysql_value(&Value::Text("a".to_owned())),
+            MyValue::from("a")
+        );
+        assert_eq!(
+            to_mysql_value(&Value::Json(serde_json::json!({"a": 1}))),
+            MyValue::from(r#"{"a":1}"#)
+        );
+    }
+}
@@ -80,13 +80,18 @@ pub fn schema_from_columns(columns: &[Column]) -> Schema {
 }
 
 impl MyRecordStream {
-    pub async fn query(conn: MyClient, query: String) -> PgWireResult<Self> {
+    pub async fn query(
+        conn: MyClient,
+        query: String,
+        params: mysql_async::Params,
+    ) -> PgWireResult<Self> {
         let (send, mut recv) = mpsc::channel::<client::Response>(1);
         conn.chan
-            .send(client::Message {
+            .send(client::Request::Query(client::Message {
                 query,
+                params,
                 response: send,
-            })
+            }))
             .await
             .ok();
 
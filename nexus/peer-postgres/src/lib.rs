@@ -2,20 +2,26 @@ use std::sync::Arc;
 
 use peer_cursor::{QueryExecutor, QueryOutput, Schema};
 use pgwire::{
-    api::results::{FieldFormat, FieldInfo},
+    api::{
+        Type,
+        results::{FieldFormat, FieldInfo},
+    },
     error::{PgWireError, PgWireResult},
 };
 use pt::peerdb_peers::PostgresConfig;
 use sqlparser::ast::Statement;
-use tokio_postgres::Client;
+use tokio_postgres::{Client, Column};
+use value::Value;
 
 pub mod ast;
+pub mod params;
 pub mod stream;
 
 // PostgresQueryExecutor is a QueryExecutor that uses a Postgres database as its
 // backing store.
 pub struct PostgresQueryExecutor {
     peername: String,
+    config: PostgresConfig,
     client: Client,
     session: Option<ssh2::Session>,
 }
@@ -25,6 +31,7 @@ impl PostgresQueryExecutor {
         let (client, session) = postgres_connection::connect_postgres(config).await?;
         Ok(Self {
             peername,
+            config: config.clone(),
             client,
             session,
         })
@@ -39,11 +46,8 @@ impl Drop for PostgresQueryExecutor {
     }
 }
 
-async fn schema_from_query(client: &Client, query: &str) -> anyhow::Result<Schema> {
-    let prepared = client.prepare_typed(query, &[]).await?;
-
-    let fields: Vec<FieldInfo> = prepared
-        .columns()
+fn schema_from_columns(columns: &[Column]) -> Schema {
+    let fields: Vec<FieldInfo> = columns
         .iter()
         .map(|c| {
             let name = c.name().to_string();
@@ -51,7 +55,31 @@ async fn schema_from_query(client: &Client, query: &str) -> anyhow::Result<Schem
         })
         .collect();
 
-    Ok(Arc::new(fields))
+    Arc::new(fields)
+}
+
+async fn schema_from_query(client: &Client, query: &str) -> anyhow::Result<Schema> {
+    let prepared = client.prepare_typed(query, &[]).await?;
+    Ok(schema_from_columns(prepared.columns()))
+}
+
+// strip the peer name from the tables referenced by the statement
+fn rewrite_statement(ast: &ast::PostgresAst, stmt: &Statement) -> PgWireResult<String> {
+    match stmt {
+        Statement::Query(query) => {
+            let mut query = query.clone();
+            ast.rewrite_query(&mut query);
+            Ok(query.to_string())
+        }
+        _ => {
+            let mut rewritten_stmt = stmt.clone();
+            ast.rewrite_statement(&mut rewritten_stmt).map_err(|e| {
+                tracing::error!("error rewriting statement: {}", e);
+                PgWireError::ApiError(format!("error rewriting statement: {e}").into())
+            })?;
+            Ok(rewritten_stmt.to_string())
+        }
+    }
 }
 
 pub async fn pg_execute_raw(client: &Client, query: &str) -> PgWireResult<QueryOutput> {
@@ -90,21 +118,10 @@ pub async fn pg_execute(
     // if the query is a select statement, we need to fetch the rows
     // and return them as a QueryOutput::Stream, else we return the
     // number of affected rows.
+    let rewritten_query = rewrite_statement(&ast, stmt)?;
     match stmt {
-        Statement::Query(query) => {
-            let mut query = query.clone();
-            ast.rewrite_query(&mut query);
-            let rewritten_query = query.to_string();
-
-            pg_execute_raw(client, &rewritten_query).await
-        }
+        Statement::Query(_) => pg_execute_raw(client, &rewritten_query).await,
         _ => {
-            let mut rewritten_stmt = stmt.clone();
-            ast.rewrite_statement(&mut rewritten_stmt).map_err(|e| {
-                tracing::error!("error rewriting statement: {}", e);
-                PgWireError::ApiError(format!("error rewriting statement: {e}").into())
-            })?;
-            let rewritten_query = rewritten_stmt.to_string();
             tracing::info!("[peer-postgres] rewritten statement: {}", rewritten_query);
             let rows_affected = client.execute(&rewritten_query, &[]).await.map_err(|e| {
                 tracing::error!("error executing query: {}", e);
@@ -115,6 +132,59 @@ pub async fn pg_execute(
     }
 }
 
+// run the statement as a prepared statement so that the parameters are bound
+// by postgres itself rather than spliced into the query text.
+pub async fn pg_execute_with_params(
+    client: &Client,
+    ast: ast::PostgresAst,
+    stmt: &Statement,
+    params: &[Value],
+) -> PgWireResult<QueryOutput> {
+    let rewritten_query = rewrite_statement(&ast, stmt)?;
+    tracing::info!(
+        "[peer-postgres] rewritten statement: {}, params: {}",
+        rewritten_query,
+        params.len()
+    );
+    let prepared = client.prepare(&rewritten_query).await.map_err(|e| {
+        tracing::error!("error preparing statement: {}", e);
+        PgWireError::ApiError(format!("error preparing statement: {e}").into())
+    })?;
+    let params = params.iter().map(params::PgParam);
+
+    match stmt {
+        Statement::Query(_) => {
+            let schema = schema_from_columns(prepared.columns());
+            let stream = client.query_raw(&prepared, params).await.map_err(|e| {
+                tracing::error!("error executing query: {}", e);
+                PgWireError::ApiError(format!("error executing query: {e}").into())
+            })?;
+            let cursor = stream::PgRecordStream::new(stream, schema);
+            Ok(QueryOutput::Stream(Box::pin(cursor)))
+        }
+        _ => {
+            let rows_affected = client.execute_raw(&prepared, params).await.map_err(|e| {
+                tracing::error!("error executing query: {}", e);
+                PgWireError::ApiError(format!("error executing query: {e}").into())
+            })?;
+            Ok(QueryOutput::AffectedRows(rows_affected as usize))
+        }
+    }
+}
+
+pub async fn pg_describe_params(
+    client: &Client,
+    ast: ast::PostgresAst,
+    stmt: &Statement,
+) -> PgWireResult<Option<Vec<Type>>> {
+    let rewritten_query = rewrite_statement(&ast, stmt)?;
+    let prepared = client.prepare(&rewritten_query).await.map_err(|e| {
+        tracing::error!("error preparing statement: {}", e);
+        PgWireError::ApiError(format!("error preparing statement: {e}").into())
+    })?;
+    Ok(Some(prepared.params().to_vec()))
+}
+
 pub async fn pg_describe(client: &Client, stmt: &Statement) -> PgWireResult<Option<Schema>> {
     match stmt {
         Statement::Query(_query) => {
@@ -130,6 +200,15 @@ pub async fn pg_describe(client: &Client, stmt: &Statement) -> PgWireResult<Opti
     }
 }
 
+pub async fn pg_cancel(client: &Client, config: &PostgresConfig) -> PgWireResult<()> {
+    postgres_connection::cancel_postgres(config, client.cancel_token())
+        .await
+        .map_err(|e| {
+            tracing::error!("error cancelling query: {}", e);
+            PgWireError::ApiError(format!("error cancelling query: {e}").into())
+        })
+}
+
 #[async_trait::async_trait]
 impl QueryExecutor for PostgresQueryExecutor {
     async fn execute_raw(&self, query: &str) -> PgWireResult<QueryOutput> {
@@ -148,7 +227,53 @@ impl QueryExecutor for PostgresQueryExecutor {
         .await
     }
 
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[Value],
+    ) -> PgWireResult<QueryOutput> {
+        pg_execute_with_params(
+            &self.client,
+            ast::PostgresAst {
+                peername: Some(self.peername.clone()),
+            },
+            stmt,
+            params,
+        )
+        .await
+    }
+
     async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
         pg_describe(&self.client, stmt).await
     }
+
+    async fn describe_params(&self, stmt: &Statement) -> PgWireResult<Option<Vec<Type>>> {
+        pg_describe_params(
+            &self.client,
+            ast::PostgresAst {
+                peername: Some(self.peername.clone()),
+            },
+            stmt,
+        )
+        .await
+    }
+
+    async fn cancel(&self) -> PgWireResult<()> {
+        pg_cancel(&self.client, &self.config).await
+    }
+
+    // an unfinished transaction is rolled back, DISCARD ALL cannot run inside one
+    async fn reset(&self) -> PgWireResult<()> {
+        for query in ["ROLLBACK", "DISCARD ALL"] {
+            self.client.batch_execute(query).await.map_err(|e| {
+                tracing::error!("error resetting connection: {}", e);
+                PgWireError::ApiError(format!("error resetting connection: {e}").into())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.client.is_closed()
+    }
 }
@@ -0,0 +1,163 @@
+use std::{error::Error, str::FromStr};
+
+use bytes::BytesMut;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use tokio_postgres::types::{IsNull, ToSql, Type, to_sql_checked};
+use value::Value;
+
+type BoxError = Box<dyn Error + Sync + Send>;
+
+/// Binds a nexus value to a parameter of the type postgres inferred for it.
+///
+/// Parameters sent in text format arrive as text and are parsed here, numbers
+/// are converted between widths as long as they fit.
+#[derive(Debug)]
+pub struct PgParam<'a>(pub &'a Value);
+
+fn int_to_sql(v: i64, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+    match *ty {
+        Type::INT2 => i16::try_from(v)?.to_sql(ty, out),
+        Type::INT4 => i32::try_from(v)?.to_sql(ty, out),
+        Type::INT8 => v.to_sql(ty, out),
+        Type::OID => u32::try_from(v)?.to_sql(ty, out),
+        Type::FLOAT4 => (v as f32).to_sql(ty, out),
+        Type::FLOAT8 => (v as f64).to_sql(ty, out),
+        Type::NUMERIC => Decimal::from(v).to_sql(ty, out),
+        _ => v.to_string().to_sql_checked(ty, out),
+    }
+}
+
+fn float_to_sql(v: f64, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+    match *ty {
+        Type::FLOAT4 => (v as f32).to_sql(ty, out),
+        Type::FLOAT8 => v.to_sql(ty, out),
+        Type::NUMERIC => Decimal::try_from(v)?.to_sql(ty, out),
+        _ => v.to_string().to_sql_checked(ty, out),
+    }
+}
+
+fn text_to_sql(s: &str, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+    match *ty {
+        Type::BOOL => match s.to_ascii_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => true.to_sql(ty, out),
+            "f" | "false" | "n" | "no" | "off" | "0" => false.to_sql(ty, out),
+            _ => Err(format!("invalid input for type boolean: {s:?}").into()),
+        },
+        Type::INT2 | Type::INT4 | Type::INT8 | Type::OID => int_to_sql(s.trim().parse()?, ty, out),
+        Type::FLOAT4 | Type::FLOAT8 => float_to_sql(s.trim().parse()?, ty, out),
+        Type::NUMERIC => Decimal::from_str(s.trim())?.to_sql(ty, out),
+        Type::DATE => NaiveDate::from_str(s)?.to_sql(ty, out),
+        Type::TIME => NaiveTime::from_str(s)?.to_sql(ty, out),
+        Type::TIMESTAMP => NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::from_str(s))?
+            .to_sql(ty, out),
+        Type::TIMESTAMPTZ => DateTime::<FixedOffset>::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%#z")
+            .or_else(|_| DateTime::parse_from_rfc3339(s))?
+            .to_sql(ty, out),
+        Type::UUID => uuid::Uuid::from_str(s)?.to_sql(ty, out),
+        Type::JSON | Type::JSONB => serde_json::Value::from_str(s)?.to_sql(ty, out),
+        _ => s.to_sql_checked(ty, out),
+    }
+}
+
+impl ToSql for PgParam<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, BoxError> {
+        match self.0 {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(v) => v.to_sql_checked(ty, out),
+            Value::TinyInt(v) => int_to_sql(*v as i64, ty, out),
+            Value::SmallInt(v) => int_to_sql(*v as i64, ty, out),
+            Value::Oid(v) => int_to_sql(*v as i64, ty, out),
+            Value::Integer(v) => int_to_sql(*v as i64, ty, out),
+            Value::BigInt(v) => int_to_sql(*v, ty, out),
+            Value::Float(v) => float_to_sql(*v as f64, ty, out),
+            Value::Double(v) => float_to_sql(*v, ty, out),
+            Value::Numeric(v) => match *ty {
+                Type::NUMERIC => v.to_sql(ty, out),
+                _ => text_to_sql(&v.to_string(), ty, out),
+            },
+            Value::Char(v) => text_to_sql(&v.to_string(), ty, out),
+            Value::VarChar(s) | Value::Text(s) | Value::Enum(s) => text_to_sql(s, ty, out),
+            Value::Binary(b) | Value::VarBinary(b) => b.as_ref().to_sql_checked(ty, out),
+            Value::Date(v) => v.to_sql_checked(ty, out),
+            Value::Time(v) | Value::TimeWithTimeZone(v) => v.to_sql_checked(ty, out),
+            Value::PostgresTimestamp(v) => v.to_sql_checked(ty, out),
+            Value::Timestamp(v) | Value::TimestampWithTimeZone(v) => match *ty {
+                Type::TIMESTAMP => v.naive_utc().to_sql(ty, out),
+                _ => v.to_sql_checked(ty, out),
+            },
+            Value::Uuid(v) => v.to_sql_checked(ty, out),
+            Value::Json(v) | Value::JsonB(v) => v.to_sql_checked(ty, out),
+            v @ (Value::IpAddr(_) | Value::Interval(_) | Value::Array(_) | Value::Hstore(_)) => {
+                Err(format!("unsupported parameter value: {v:?}").into())
+            }
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        // conversion to the parameter type is checked in to_sql
+        true
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: Value, ty: Type) -> Result<Option<Vec<u8>>, BoxError> {
+        let mut out = BytesMut::new();
+        match PgParam(&value).to_sql(&ty, &mut out)? {
+            IsNull::Yes => Ok(None),
+            IsNull::No => Ok(Some(out.to_vec())),
+        }
+    }
+
+    #[test]
+    fn null() {
+        assert_eq!(encode(Value::Null, Type::INT4).unwrap(), None);
+    }
+
+    #[test]
+    fn ints_are_converted_between_widths() {
+        assert_eq!(
+            encode(Value::BigInt(5), Type::INT4).unwrap(),
+            Some(5i32.to_be_bytes().to_vec())
+        );
+        assert_eq!(
+            encode(Value::SmallInt(5), Type::INT8).unwrap(),
+            Some(5i64.to_be_bytes().to_vec())
+        );
+        assert!(encode(Value::BigInt(70_000), Type::INT2).is_err());
+        assert_eq!(
+            encode(Value::Integer(7), Type::TEXT).unwrap(),
+            Some(b"7".to_vec())
+        );
+    }
+
+    #[test]
+    fn text_is_parsed_as_the_parameter_type() {
+        assert_eq!(
+            encode(Value::Text(" 42 ".to_owned()), Type::INT8).unwrap(),
+            Some(42i64.to_be_bytes().to_vec())
+        );
+        assert_eq!(
+            encode(Value::Text("Yes".to_owned()), Type::BOOL).unwrap(),
+            Some(vec![1])
+        );
+        assert_eq!(
+            encode(Value::Text("abc".to_owned()), Type::TEXT).unwrap(),
+            Some(b"abc".to_vec())
+        );
+        assert!(encode(Value::Text("maybe".to_owned()), Type::BOOL).is_err());
+        assert!(encode(Value::Text("4.2".to_owned()), Type::INT4).is_err());
+    }
+
+    #[test]
+    fn mismatched_types_are_rejected() {
+        assert!(encode(Value::Bool(true), Type::INT4).is_err());
+        assert!(encode(Value::Interval(1), Type::INTERVAL).is_err());
+    }
+}
@@ -2,6 +2,8 @@ use anyhow::Context;
 use peer_cursor::{CursorManager, CursorModification, QueryExecutor, QueryOutput, Schema};
 use pgwire::error::{ErrorInfo, PgWireError, PgWireResult};
 use std::cmp::min;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 use stream::SnowflakeDataType;
 
@@ -39,6 +41,13 @@ struct SQLStatementParameters<'a> {
     pub timestamp_tz_output_format: &'a str,
 }
 
+// bind variable of the SQL API, value is in the string form of the type
+#[derive(Debug, Serialize)]
+struct SQLBinding {
+    r#type: &'static str,
+    value: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 struct SQLStatement<'a> {
     statement: &'a str,
@@ -47,6 +56,34 @@ struct SQLStatement<'a> {
     warehouse: &'a str,
     role: &'a str,
     parameters: SQLStatementParameters<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bindings: Option<&'a HashMap<String, SQLBinding>>,
+}
+
+fn to_binding(value: &value::Value) -> SQLBinding {
+    use value::Value;
+    let (r#type, value) = match value {
+        Value::Null => ("TEXT", None),
+        Value::Bool(v) => ("BOOLEAN", Some(v.to_string())),
+        Value::TinyInt(v) => ("FIXED", Some(v.to_string())),
+        Value::SmallInt(v) => ("FIXED", Some(v.to_string())),
+        Value::Oid(v) => ("FIXED", Some(v.to_string())),
+        Value::Integer(v) => ("FIXED", Some(v.to_string())),
+        Value::BigInt(v) => ("FIXED", Some(v.to_string())),
+        Value::Numeric(v) => ("FIXED", Some(v.to_string())),
+        Value::Float(v) => ("REAL", Some(v.to_string())),
+        Value::Double(v) => ("REAL", Some(v.to_string())),
+        Value::Binary(b) | Value::VarBinary(b) => ("BINARY", Some(hex::encode(b))),
+        Value::Json(_) | Value::JsonB(_) | Value::Array(_) | Value::Hstore(_) => {
+            ("TEXT", Some(value.to_serde_json_value().to_string()))
+        }
+        // dates and times are bound as text and cast by snowflake
+        _ => match value.to_serde_json_value() {
+            serde_json::Value::String(s) => ("TEXT", Some(s)),
+            v => ("TEXT", Some(v.to_string())),
+        },
+    };
+    SQLBinding { r#type, value }
 }
 
 #[allow(non_snake_case)]
@@ -99,6 +136,8 @@ pub struct SnowflakeQueryExecutor {
     query_timeout: u64,
     reqwest_client: reqwest::Client,
     cursor_manager: CursorManager,
+    // handle of the statement being polled, used to cancel it
+    statement_handle: Mutex<Option<String>>,
 }
 
 enum QueryAttemptResult {
@@ -144,11 +183,16 @@ impl SnowflakeQueryExecutor {
             query_timeout: config.query_timeout,
             reqwest_client,
             cursor_manager: Default::default(),
+            statement_handle: Mutex::new(None),
         })
     }
 
     #[tracing::instrument(name = "peer_sflake::process_query", skip_all)]
-    async fn process_query(&self, query_str: &str) -> anyhow::Result<ResultSet> {
+    async fn process_query(
+        &self,
+        query_str: &str,
+        bindings: Option<&HashMap<String, SQLBinding>>,
+    ) -> anyhow::Result<ResultSet> {
         loop {
             let mut auth = self.auth.clone();
             let jwt = auth.get_jwt()?;
@@ -174,6 +218,7 @@ impl SnowflakeQueryExecutor {
                         timestamp_ntz_output_format: TIMESTAMP_OUTPUT_FORMAT,
                         timestamp_tz_output_format: TIMESTAMP_TZ_OUTPUT_FORMAT,
                     },
+                    bindings,
                 })
                 .send()
                 .await
@@ -186,8 +231,12 @@ impl SnowflakeQueryExecutor {
                 anyhow::anyhow!("failed in parsing json {:?}, error: {:?}", query_json, e)
             })?;
 
+            *self.statement_handle.lock().unwrap() = Some(query_status.statementHandle.clone());
+            let res = self.query_poll(query_status).await;
+            self.statement_handle.lock().unwrap().take();
+
             // TODO: remove this blind retry logic for anything other than a SELECT.
-            if let Some(res) = self.query_poll(query_status).await? {
+            if let Some(res) = res? {
                 return Ok(res);
             }
         }
@@ -202,7 +251,7 @@ impl SnowflakeQueryExecutor {
         info!("Processing SnowFlake query: {}", query_str);
 
         let result_set = self
-            .process_query(&query_str)
+            .process_query(&query_str, None)
             .await
             .map_err(|err| PgWireError::ApiError(err.into()))?;
         Ok(result_set)
@@ -246,6 +295,26 @@ impl SnowflakeQueryExecutor {
         }
     }
 
+    async fn cancel_statement(&self, statement_handle: &str) -> anyhow::Result<()> {
+        let mut auth = self.auth.clone();
+        let jwt = auth.get_jwt()?;
+        let secret = jwt.expose_secret();
+        let response = self
+            .reqwest_client
+            .post(format!("{}/{}/cancel", self.endpoint_url, statement_handle))
+            .bearer_auth(secret)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "failed to cancel statement {}: {}",
+                statement_handle,
+                response.text().await?
+            ));
+        }
+        Ok(())
+    }
+
     #[tracing::instrument(name = "peer_sflake::query_poll", skip_all)]
     async fn query_poll(&self, query_status: QueryStatus) -> anyhow::Result<Option<ResultSet>> {
         info!(
@@ -292,7 +361,7 @@ impl SnowflakeQueryExecutor {
 impl QueryExecutor for SnowflakeQueryExecutor {
     async fn execute_raw(&self, query: &str) -> PgWireResult<QueryOutput> {
         let result_set = self
-            .process_query(query)
+            .process_query(query, None)
             .await
             .map_err(|err| PgWireError::ApiError(err.into()))?;
 
@@ -413,6 +482,58 @@ impl QueryExecutor for SnowflakeQueryExecutor {
         }
     }
 
+    async fn execute_with_params(
+        &self,
+        stmt: &Statement,
+        params: &[value::Value],
+    ) -> PgWireResult<QueryOutput> {
+        match stmt {
+            Statement::Query(query) => {
+                let mut query = query.clone();
+                ast::SnowflakeAst
+                    .rewrite(&mut query)
+                    .context("unable to rewrite query")
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+                // snowflake refers to bind variables by position as `:n`
+                peer_ast::rewrite_placeholders(&mut query, |idx| format!(":{idx}"))
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+
+                let bindings = params
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, value)| ((idx + 1).to_string(), to_binding(value)))
+                    .collect::<HashMap<_, _>>();
+                let query_str = query.to_string();
+                info!("Processing SnowFlake query: {}", query_str);
+
+                let result_set = self
+                    .process_query(&query_str, Some(&bindings))
+                    .await
+                    .map_err(|err| PgWireError::ApiError(err.into()))?;
+
+                let cursor = stream::SnowflakeRecordStream::new(
+                    result_set,
+                    self.partition_index,
+                    self.partition_number,
+                    self.endpoint_url.clone(),
+                    self.auth.clone(),
+                );
+                Ok(QueryOutput::Stream(Box::pin(cursor)))
+            }
+            _ if params.is_empty() => self.execute(stmt).await,
+            _ => {
+                let error = format!(
+                    "only SELECT statements support parameters in snowflake. got: {stmt}"
+                );
+                PgWireResult::Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "fdw_error".to_owned(),
+                    error,
+                ))))
+            }
+        }
+    }
+
     async fn describe(&self, stmt: &Statement) -> PgWireResult<Option<Schema>> {
         match stmt {
             Statement::Query(query) => {
@@ -452,4 +573,19 @@ impl QueryExecutor for SnowflakeQueryExecutor {
             )))),
         }
     }
+
+    async fn cancel(&self) -> PgWireResult<()> {
+        let Some(statement_handle) = self.statement_handle.lock().unwrap().clone() else {
+            return Ok(());
+        };
+        info!("Cancelling SnowFlake statement: {}", statement_handle);
+        self.cancel_statement(&statement_handle)
+            .await
+            .map_err(|err| PgWireError::ApiError(err.into()))
+    }
+
+    async fn reset(&self) -> PgWireResult<()> {
+        self.cursor_manager.close_all_cursors().await?;
+        Ok(())
+    }
 }
@@ -132,6 +132,19 @@ pub async fn create_tunnel(
     Ok((session, stream2))
 }
 
+pub fn make_tls_connector() -> anyhow::Result<MakeRustlsConnect> {
+    let mut tls_config = ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_protocol_versions(&[&rustls::version::TLS13])?
+    .with_root_certificates(RootCertStore::empty())
+    .with_no_client_auth();
+    tls_config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(NoCertificateVerification));
+    Ok(MakeRustlsConnect::new(tls_config))
+}
+
 pub async fn connect_postgres(
     config: &PostgresConfig,
 ) -> anyhow::Result<(tokio_postgres::Client, Option<ssh2::Session>)> {
@@ -155,17 +168,7 @@ pub async fn connect_postgres(
         Ok((client, Some(session)))
     } else {
         let connection_string = get_pg_connection_string(config);
-
-        let mut tls_config = ClientConfig::builder_with_provider(Arc::new(
-            rustls::crypto::aws_lc_rs::default_provider(),
-        ))
-        .with_protocol_versions(&[&rustls::version::TLS13])?
-        .with_root_certificates(RootCertStore::empty())
-        .with_no_client_auth();
-        tls_config
-            .dangerous()
-            .set_certificate_verifier(Arc::new(NoCertificateVerification));
-        let tls_connector = MakeRustlsConnect::new(tls_config);
+        let tls_connector = make_tls_connector()?;
         let (client, connection) = tokio_postgres::connect(&connection_string, tls_connector)
             .await
             .map_err(|e| {
@@ -179,3 +182,23 @@ pub async fn connect_postgres(
         Ok((client, None))
     }
 }
+
+// cancel requests are sent over a new connection, through a new tunnel when the
+// peer is only reachable over ssh.
+pub async fn cancel_postgres(
+    config: &PostgresConfig,
+    cancel_token: tokio_postgres::CancelToken,
+) -> anyhow::Result<()> {
+    if let Some(ssh_config) = &config.ssh_config {
+        let tcp = std::net::TcpStream::connect((ssh_config.host.as_str(), ssh_config.port as u16))?;
+        tcp.set_nodelay(true)?;
+        let (session, stream) =
+            create_tunnel(tcp, ssh_config, config.host.clone(), config.port as u16).await?;
+        let res = cancel_token.cancel_query_raw(stream, tokio_postgres::NoTls).await;
+        session.disconnect(None, "", None).ok();
+        res?;
+    } else {
+        cancel_token.cancel_query(make_tls_connector()?).await?;
+    }
+    Ok(())
+}
@@ -9,6 +9,36 @@ pub struct FlowJobTableMapping {
     pub destination_table_identifier: String,
     pub partition_key: Option<String>,
     pub exclude: Vec<String>,
+    pub settings: FlowJobTableSettings,
+}
+
+/// Settings given after a table mapping, like
+/// `public.t1:public.t1 COLUMNS (id ORDERING 1) WITH (engine = 'replacing_merge_tree')`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FlowJobTableSettings {
+    pub columns: Vec<FlowJobColumnSetting>,
+    /// ClickHouse table engine, named without its CH_ENGINE_ prefix, like merge_tree
+    pub engine: Option<String>,
+    pub sharding_key: Option<String>,
+    pub policy_name: Option<String>,
+    /// replaces the mirror's soft_delete_col_name for this table
+    pub soft_delete_col_name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct FlowJobColumnSetting {
+    pub source_name: String,
+    #[serde(default)]
+    pub destination_name: Option<String>,
+    #[serde(default)]
+    pub destination_type: Option<String>,
+    /// position of the column in the ClickHouse ordering key, 0 leaves it out
+    #[serde(default)]
+    pub ordering: i32,
+    #[serde(default)]
+    pub nullable: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
@@ -34,6 +64,9 @@ pub struct FlowJob {
     pub script: String,
     pub system: String,
     pub disable_peerdb_columns: bool,
+    pub tags: HashMap<String, String>,
+    /// dynamic settings overridden for this mirror, like PEERDB_QUEUE_FLUSH_TIMEOUT_SECONDS
+    pub env: HashMap<String, String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
@@ -45,4 +78,5 @@ pub struct QRepFlowJob {
     pub flow_options: HashMap<String, Value>,
     pub description: String,
     pub disabled: bool,
+    pub tags: HashMap<String, String>,
 }
@@ -0,0 +1,181 @@
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use catalog::{Catalog, NexusUser};
+use futures::Sink;
+use pgwire::{
+    api::{
+        ClientInfo,
+        auth::{
+            AuthSource, LoginInfo, Password, ServerParameterProvider, StartupHandler,
+            finish_authentication, save_startup_parameters_to_metadata,
+            scram::gen_salted_password,
+        },
+    },
+    error::{ErrorInfo, PgWireError, PgWireResult},
+    messages::{PgWireBackendMessage, PgWireFrontendMessage},
+};
+use rand::Rng;
+use x509_parser::{extensions::GeneralName, prelude::*};
+
+// must match the iteration count used by the SCRAM startup handler
+const SCRAM_ITERATIONS: usize = 4096;
+
+/// Looks up the SCRAM verifier of the connecting user in the catalog.
+///
+/// Users that are not in the catalog may log in with the shared
+/// `PEERDB_PASSWORD`, unless that has been disabled.
+pub struct CatalogAuthSource {
+    catalog: Arc<Catalog>,
+    shared_password: String,
+    allow_shared_password: bool,
+}
+
+impl CatalogAuthSource {
+    pub fn new(
+        catalog: Arc<Catalog>,
+        shared_password: String,
+        allow_shared_password: bool,
+    ) -> Self {
+        Self {
+            catalog,
+            shared_password,
+            allow_shared_password,
+        }
+    }
+
+    async fn has_user(&self, user_name: &str) -> PgWireResult<bool> {
+        let user = self.catalog.get_user(user_name).await.map_err(|err| {
+            PgWireError::ApiError(format!("unable to query catalog for user: {err:?}").into())
+        })?;
+        Ok(user.is_some())
+    }
+}
+
+#[async_trait]
+impl AuthSource for CatalogAuthSource {
+    async fn get_password(&self, login_info: &LoginInfo) -> PgWireResult<Password> {
+        let user_name = login_info.user().unwrap_or_default();
+        tracing::info!("authenticating user: {}", user_name);
+
+        let user = self.catalog.get_user(user_name).await.map_err(|err| {
+            PgWireError::ApiError(format!("unable to query catalog for user: {err:?}").into())
+        })?;
+        if let Some(user) = user {
+            return Ok(Password::new(Some(user.salt), user.salted_password));
+        }
+
+        if !self.allow_shared_password {
+            tracing::warn!("rejecting unknown user: {}", user_name);
+            return Err(PgWireError::InvalidPassword(user_name.to_owned()));
+        }
+
+        // randomly generate a 4 byte salt
+        let salt = rand::rng().random::<[u8; 4]>();
+        let hash_password = gen_salted_password(&self.shared_password, &salt, SCRAM_ITERATIONS);
+        Ok(Password::new(Some(salt.to_vec()), hash_password))
+    }
+}
+
+/// Builds the catalog entry for a user, the plaintext password is not stored.
+pub fn new_user(name: String, password: &str) -> NexusUser {
+    let salt = rand::rng().random::<[u8; 16]>().to_vec();
+    let salted_password = gen_salted_password(password, &salt, SCRAM_ITERATIONS);
+    NexusUser {
+        name,
+        salt,
+        salted_password,
+    }
+}
+
+/// How client certificates presented over TLS are used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientCertAuth {
+    /// Client certificates are not requested.
+    Disabled,
+    /// Connections without a certificate signed by the client CA are rejected.
+    Required,
+    /// As `Required`, and a certificate whose CN or SAN is the name of the
+    /// connecting nexus user logs it in without a password.
+    MapToUser,
+}
+
+/// Checks the client certificate before handing the startup over to `inner`.
+pub struct ClientCertStartupHandler<H, P> {
+    pub inner: H,
+    pub auth_source: Arc<CatalogAuthSource>,
+    pub parameter_provider: Arc<P>,
+    pub mode: ClientCertAuth,
+}
+
+// the common names and the dns and email alternative names of a certificate
+fn certificate_names(der: &[u8]) -> Vec<String> {
+    let Ok((_, cert)) = X509Certificate::from_der(der) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|cn| cn.as_str().ok())
+        .map(String::from)
+        .collect();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            if let GeneralName::DNSName(name) | GeneralName::RFC822Name(name) = name {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+#[async_trait]
+impl<H: StartupHandler, P: ServerParameterProvider> StartupHandler
+    for ClientCertStartupHandler<H, P>
+{
+    async fn on_startup<C>(
+        &self,
+        client: &mut C,
+        message: PgWireFrontendMessage,
+    ) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        if self.mode == ClientCertAuth::Disabled {
+            return self.inner.on_startup(client, message).await;
+        }
+
+        if let PgWireFrontendMessage::Startup(ref startup) = message {
+            let cert_names = client
+                .client_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| certificate_names(cert.as_ref()));
+            let Some(cert_names) = cert_names else {
+                return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "FATAL".to_owned(),
+                    "28000".to_owned(),
+                    "connection requires a valid client certificate".to_owned(),
+                ))));
+            };
+
+            // a certificate that does not name the user falls back to a password login
+            let user_name = startup
+                .parameters
+                .get("user")
+                .map(String::as_str)
+                .unwrap_or_default();
+            if self.mode == ClientCertAuth::MapToUser
+                && cert_names.iter().any(|name| name == user_name)
+                && self.auth_source.has_user(user_name).await?
+            {
+                tracing::info!("authenticated user {} by client certificate", user_name);
+                save_startup_parameters_to_metadata(client, startup);
+                finish_authentication(client, self.parameter_provider.as_ref()).await?;
+                return Ok(());
+            }
+        }
+        self.inner.on_startup(client, message).await
+    }
+}
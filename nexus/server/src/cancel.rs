@@ -0,0 +1,178 @@
+use std::{
+    fmt::Debug,
+    io,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicI32, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bytes::Buf;
+use dashmap::DashMap;
+use futures::Sink;
+use pgwire::{
+    api::{ClientInfo, auth::StartupHandler},
+    error::{PgWireError, PgWireResult},
+    messages::{PgWireBackendMessage, PgWireFrontendMessage, startup::SecretKey},
+};
+use tokio::{io::AsyncReadExt, net::TcpStream};
+
+use crate::NexusBackend;
+
+const CANCEL_REQUEST_LEN: usize = 16;
+const CANCEL_REQUEST_CODE: i32 = 80877102;
+// the same as postgres' default authentication_timeout
+const FIRST_PACKET_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Cancel keys handed out to the running sessions, a cancel request quoting
+/// the key of a session cancels what that session is running on its peers.
+#[derive(Default)]
+pub struct CancelKeys {
+    next_pid: AtomicI32,
+    sessions: DashMap<i32, (i32, Weak<NexusBackend>)>,
+}
+
+impl CancelKeys {
+    pub fn register(&self, nexus: &Arc<NexusBackend>) -> (i32, i32) {
+        let pid = self.next_pid.fetch_add(1, Ordering::Relaxed) + 1;
+        let secret_key = rand::random::<i32>();
+        self.sessions.insert(pid, (secret_key, Arc::downgrade(nexus)));
+        (pid, secret_key)
+    }
+
+    pub fn unregister(&self, pid: i32) {
+        self.sessions.remove(&pid);
+    }
+
+    pub async fn cancel(&self, pid: i32, secret_key: i32) {
+        let nexus = match self.sessions.get(&pid) {
+            Some(session) if session.0 == secret_key => session.1.upgrade(),
+            _ => None,
+        };
+        if let Some(nexus) = nexus {
+            tracing::info!("cancelling queries of session {}", pid);
+            nexus.cancel().await;
+        } else {
+            tracing::warn!("ignoring cancel request for unknown session {}", pid);
+        }
+    }
+}
+
+/// Consume the first packet of a connection if it is a cancel request,
+/// returning the pid and secret key it carries. Clients send their first packet
+/// right away, connections which do not within the timeout are given up on.
+pub async fn read_cancel_request(socket: &mut TcpStream) -> io::Result<Option<(i32, i32)>> {
+    tokio::time::timeout(FIRST_PACKET_TIMEOUT, peek_cancel_request(socket))
+        .await
+        .map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                "no startup packet received before the timeout",
+            )
+        })?
+}
+
+async fn peek_cancel_request(socket: &mut TcpStream) -> io::Result<Option<(i32, i32)>> {
+    // the length and code tell a cancel request apart from the other startup packets
+    let mut header = [0u8; 8];
+    loop {
+        let read = socket.peek(&mut header).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read >= 4 && header[..4] != (CANCEL_REQUEST_LEN as i32).to_be_bytes() {
+            return Ok(None);
+        }
+        if read == header.len() {
+            break;
+        }
+        // peek returns right away while anything is buffered, give the rest time to arrive
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    if header[4..] != CANCEL_REQUEST_CODE.to_be_bytes() {
+        return Ok(None);
+    }
+
+    // a cancel request it is, wait for the whole of it
+    let mut packet = [0u8; CANCEL_REQUEST_LEN];
+    socket.read_exact(&mut packet).await?;
+    let mut buf = &packet[8..];
+    let pid = buf.get_i32();
+    let secret_key = buf.get_i32();
+    Ok(Some((pid, secret_key)))
+}
+
+/// Sets the cancel key sent to the client in `BackendKeyData` before handing
+/// the startup over to `inner`.
+pub struct CancelKeyStartupHandler<H> {
+    pub inner: H,
+    pub pid: i32,
+    pub secret_key: i32,
+}
+
+#[async_trait]
+impl<H: StartupHandler> StartupHandler for CancelKeyStartupHandler<H> {
+    async fn on_startup<C>(
+        &self,
+        client: &mut C,
+        message: PgWireFrontendMessage,
+    ) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        if let PgWireFrontendMessage::Startup(_) = message {
+            client.set_pid_and_secret_key(self.pid, SecretKey::I32(self.secret_key));
+        }
+        self.inner.on_startup(client, message).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+    use super::*;
+
+    // the server side of a connection on which the client sent `packet`
+    async fn server_socket(packet: &[u8]) -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        client.write_all(packet).await.unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        (socket, client)
+    }
+
+    #[tokio::test]
+    async fn cancel_request_is_read() {
+        let mut packet = Vec::new();
+        packet.extend((CANCEL_REQUEST_LEN as i32).to_be_bytes());
+        packet.extend(CANCEL_REQUEST_CODE.to_be_bytes());
+        packet.extend(7i32.to_be_bytes());
+        packet.extend((-42i32).to_be_bytes());
+        let (mut socket, _client) = server_socket(&packet).await;
+        assert_eq!(
+            read_cancel_request(&mut socket).await.unwrap(),
+            Some((7, -42))
+        );
+    }
+
+    #[tokio::test]
+    async fn startup_packet_is_left_unread() {
+        // an SSLRequest, which has the length of a cancel request but another code
+        let mut packet = Vec::new();
+        packet.extend(8i32.to_be_bytes());
+        packet.extend(80877103i32.to_be_bytes());
+        let (mut socket, _client) = server_socket(&packet).await;
+        assert_eq!(read_cancel_request(&mut socket).await.unwrap(), None);
+
+        let mut unread = [0u8; 8];
+        socket.read_exact(&mut unread).await.unwrap();
+        assert_eq!(unread[..], packet[..]);
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use peer_connections::{PeerConnectionTracker, PeerConnections};
+use peer_cursor::QueryExecutor;
+use pt::peerdb_peers::{Peer, peer::Config};
+
+/// Pools of peer executors shared by all sessions. A session checks an executor out for
+/// its own statements and hands it back when it ends, so that ssh tunnels, snowflake and
+/// bigquery authentication are reused by the next client, while no two sessions ever run
+/// statements on the same connection.
+pub struct ExecutorRegistry {
+    pools: DashMap<String, PeerPool>,
+    peer_connections: Arc<PeerConnections>,
+    idle_timeout: Duration,
+}
+
+struct PeerPool {
+    // the peer as the pooled executors were created for, a changed config empties the pool
+    peer: Peer,
+    idle: Vec<IdleExecutor>,
+    // executors in use by sessions, only these are taken back. Invalidating the peer drops
+    // the pool, so the executors of the old config are dropped once they are checked in.
+    checked_out: Vec<Weak<dyn QueryExecutor>>,
+}
+
+impl PeerPool {
+    fn new(peer: &Peer) -> Self {
+        Self {
+            peer: peer.clone(),
+            idle: Vec::new(),
+            checked_out: Vec::new(),
+        }
+    }
+
+    fn check_out(&mut self, executor: &Arc<dyn QueryExecutor>) {
+        // sessions drop executors without checking them in when their connection is gone
+        self.checked_out
+            .retain(|checked_out| checked_out.strong_count() > 0);
+        self.checked_out.push(Arc::downgrade(executor));
+    }
+
+    // whether the executor was checked out of this pool
+    fn check_in(&mut self, executor: &Arc<dyn QueryExecutor>) -> bool {
+        let executor = Arc::downgrade(executor);
+        let checked_out = self.checked_out.len();
+        self.checked_out
+            .retain(|checked_out| !checked_out.ptr_eq(&executor));
+        self.checked_out.len() < checked_out
+    }
+}
+
+struct IdleExecutor {
+    executor: Arc<dyn QueryExecutor>,
+    idle_since: Instant,
+}
+
+impl ExecutorRegistry {
+    pub fn new(peer_connections: Arc<PeerConnections>, idle_timeout: Duration) -> Self {
+        Self {
+            pools: DashMap::new(),
+            peer_connections,
+            idle_timeout,
+        }
+    }
+
+    /// Take an idle executor of the peer, or connect a new one if there is none.
+    pub async fn checkout(&self, peer: &Peer) -> anyhow::Result<Arc<dyn QueryExecutor>> {
+        if let Some(executor) = self.take_idle(peer) {
+            return Ok(executor);
+        }
+
+        // connect without holding the map, peers can take a while to connect to
+        let tracker =
+            PeerConnectionTracker::new(uuid::Uuid::new_v4(), self.peer_connections.clone());
+        let executor = create_executor(peer, tracker).await?;
+        tracing::info!("created executor for peer {}", peer.name);
+        self.track(peer, &executor);
+        Ok(executor)
+    }
+
+    fn take_idle(&self, peer: &Peer) -> Option<Arc<dyn QueryExecutor>> {
+        let mut pool = self.pools.get_mut(&peer.name)?;
+        if pool.peer != *peer {
+            *pool = PeerPool::new(peer);
+        }
+        while let Some(idle) = pool.idle.pop() {
+            if !idle.executor.is_closed() {
+                pool.check_out(&idle.executor);
+                return Some(idle.executor);
+            }
+        }
+        None
+    }
+
+    fn track(&self, peer: &Peer, executor: &Arc<dyn QueryExecutor>) {
+        let mut pool = self
+            .pools
+            .entry(peer.name.clone())
+            .or_insert_with(|| PeerPool::new(peer));
+        // the peer was changed while connecting, the executor is dropped once checked in
+        if pool.peer == *peer {
+            pool.check_out(executor);
+        }
+    }
+
+    /// Return an executor once its session is done with it. The state the session left
+    /// on the connection is reset first, executors that cannot be reset are dropped, as
+    /// are executors of a peer that was invalidated or changed since they were checked out.
+    pub async fn checkin(&self, peer: &Peer, executor: Arc<dyn QueryExecutor>) {
+        let checked_out = self
+            .pools
+            .get_mut(&peer.name)
+            .is_some_and(|mut pool| pool.peer == *peer && pool.check_in(&executor));
+        // executors of an invalidated or changed peer are not taken back, nor are ones still
+        // referenced by something of the session, they cannot be handed to another one
+        if !checked_out || executor.is_closed() || Arc::strong_count(&executor) > 1 {
+            return;
+        }
+        if let Err(err) = executor.reset().await {
+            tracing::warn!("dropping executor for peer {}: {}", peer.name, err);
+            return;
+        }
+
+        // the peer may have been invalidated while the executor was reset
+        if let Some(mut pool) = self
+            .pools
+            .get_mut(&peer.name)
+            .filter(|pool| pool.peer == *peer)
+        {
+            pool.idle.push(IdleExecutor {
+                executor,
+                idle_since: Instant::now(),
+            });
+        }
+    }
+
+    pub fn invalidate(&self, peer_name: &str) {
+        if self.pools.remove(peer_name).is_some() {
+            tracing::info!("dropped pooled executors for peer {}", peer_name);
+        }
+    }
+
+    /// Drop the pooled executors that were not checked out for the idle timeout,
+    /// executors in use by sessions are not affected. Runs until the process exits.
+    pub async fn evict_idle(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(self.idle_timeout.max(Duration::from_secs(2)) / 2);
+        loop {
+            interval.tick().await;
+            self.pools.retain(|peer_name, pool| {
+                let pooled = pool.idle.len();
+                pool.idle
+                    .retain(|idle| idle.idle_since.elapsed() < self.idle_timeout);
+                if pool.idle.len() < pooled {
+                    tracing::info!(
+                        "evicted {} idle executors for peer {}",
+                        pooled - pool.idle.len(),
+                        peer_name
+                    );
+                }
+                // the pool is kept for the executors sessions will check in
+                !pool.idle.is_empty()
+                    || pool
+                        .checked_out
+                        .iter()
+                        .any(|checked_out| checked_out.strong_count() > 0)
+            });
+        }
+    }
+}
+
+pub async fn create_executor(
+    peer: &Peer,
+    peer_connections: PeerConnectionTracker,
+) -> anyhow::Result<Arc<dyn QueryExecutor>> {
+    Ok(match &peer.config {
+        Some(Config::BigqueryConfig(c)) => {
+            let executor =
+                peer_bigquery::BigQueryQueryExecutor::new(peer.name.clone(), c, peer_connections)
+                    .await?;
+            Arc::new(executor)
+        }
+        Some(Config::MysqlConfig(c)) => {
+            let executor = peer_mysql::MySqlQueryExecutor::new(peer.name.clone(), c).await?;
+            Arc::new(executor)
+        }
+        Some(Config::PostgresConfig(c)) => {
+            let executor = peer_postgres::PostgresQueryExecutor::new(peer.name.clone(), c).await?;
+            Arc::new(executor)
+        }
+        Some(Config::SnowflakeConfig(c)) => {
+            let executor = peer_snowflake::SnowflakeQueryExecutor::new(c).await?;
+            Arc::new(executor)
+        }
+        _ => {
+            panic!("peer type not supported: {peer:?}")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use peer_cursor::{QueryOutput, Schema};
+    use pgwire::error::PgWireResult;
+    use pt::peerdb_peers::PostgresConfig;
+    use sqlparser::ast::Statement;
+    use value::Value;
+
+    use super::*;
+
+    // shared with the test, which only gets the executor back as a trait object
+    #[derive(Clone, Default)]
+    struct StubState {
+        closed: Arc<AtomicBool>,
+        resets: Arc<AtomicUsize>,
+    }
+
+    struct StubExecutor(StubState);
+
+    #[async_trait::async_trait]
+    impl QueryExecutor for StubExecutor {
+        async fn execute_raw(&self, _stmt: &str) -> PgWireResult<QueryOutput> {
+            unimplemented!()
+        }
+
+        async fn execute(&self, _stmt: &Statement) -> PgWireResult<QueryOutput> {
+            unimplemented!()
+        }
+
+        async fn execute_with_params(
+            &self,
+            _stmt: &Statement,
+            _params: &[Value],
+        ) -> PgWireResult<QueryOutput> {
+            unimplemented!()
+        }
+
+        async fn describe(&self, _stmt: &Statement) -> PgWireResult<Option<Schema>> {
+            unimplemented!()
+        }
+
+        async fn cancel(&self) -> PgWireResult<()> {
+            Ok(())
+        }
+
+        async fn reset(&self) -> PgWireResult<()> {
+            self.0.resets.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn is_closed(&self) -> bool {
+            self.0.closed.load(Ordering::Relaxed)
+        }
+    }
+
+    fn registry() -> ExecutorRegistry {
+        // the pool of the connection tracker only connects once a query is tracked
+        let peer_connections = PeerConnections::new("host=localhost").unwrap();
+        ExecutorRegistry::new(Arc::new(peer_connections), Duration::from_secs(60))
+    }
+
+    fn peer(host: &str) -> Peer {
+        Peer {
+            name: "pg".to_owned(),
+            config: Some(Config::PostgresConfig(PostgresConfig {
+                host: host.to_owned(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+    }
+
+    // an executor as checkout hands it out after connecting
+    fn connect(registry: &ExecutorRegistry, peer: &Peer) -> (Arc<dyn QueryExecutor>, StubState) {
+        let state = StubState::default();
+        let executor: Arc<dyn QueryExecutor> = Arc::new(StubExecutor(state.clone()));
+        registry.track(peer, &executor);
+        (executor, state)
+    }
+
+    fn address(executor: &Arc<dyn QueryExecutor>) -> *const () {
+        Arc::as_ptr(executor) as *const ()
+    }
+
+    #[tokio::test]
+    async fn executor_is_reused_after_checkin() {
+        let registry = registry();
+        let peer = peer("localhost");
+        let (executor, state) = connect(&registry, &peer);
+        let executor_address = address(&executor);
+        registry.checkin(&peer, executor).await;
+        assert_eq!(state.resets.load(Ordering::Relaxed), 1);
+
+        let reused = registry.take_idle(&peer).unwrap();
+        assert_eq!(address(&reused), executor_address);
+        assert!(registry.take_idle(&peer).is_none());
+
+        // and again once the next session is done with it
+        registry.checkin(&peer, reused).await;
+        assert_eq!(state.resets.load(Ordering::Relaxed), 2);
+        let reused = registry.take_idle(&peer).unwrap();
+        assert_eq!(address(&reused), executor_address);
+    }
+
+    #[tokio::test]
+    async fn executor_in_use_is_not_pooled() {
+        let registry = registry();
+        let peer = peer("localhost");
+        let (executor, _) = connect(&registry, &peer);
+        let _session = executor.clone();
+        registry.checkin(&peer, executor).await;
+        assert!(registry.take_idle(&peer).is_none());
+    }
+
+    #[tokio::test]
+    async fn closed_executor_is_dropped() {
+        let registry = registry();
+        let peer = peer("localhost");
+        let (executor, state) = connect(&registry, &peer);
+        state.closed.store(true, Ordering::Relaxed);
+        registry.checkin(&peer, executor).await;
+        assert!(registry.take_idle(&peer).is_none());
+
+        // closed while it was idle
+        let (executor, state) = connect(&registry, &peer);
+        registry.checkin(&peer, executor).await;
+        state.closed.store(true, Ordering::Relaxed);
+        assert!(registry.take_idle(&peer).is_none());
+    }
+
+    #[tokio::test]
+    async fn invalidated_peer_drops_its_executors() {
+        let registry = registry();
+        let peer = peer("localhost");
+        let (idle, _) = connect(&registry, &peer);
+        let (in_use, in_use_state) = connect(&registry, &peer);
+        registry.checkin(&peer, idle).await;
+
+        registry.invalidate(&peer.name);
+        assert!(registry.take_idle(&peer).is_none());
+        // the executor of the replaced peer is not taken back, even with the same config
+        registry.checkin(&peer, in_use).await;
+        assert!(registry.take_idle(&peer).is_none());
+        assert_eq!(in_use_state.resets.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn changed_peer_drops_its_executors() {
+        let registry = registry();
+        let old_peer = peer("localhost");
+        let new_peer = peer("otherhost");
+        let (idle, _) = connect(&registry, &old_peer);
+        let (in_use, _) = connect(&registry, &old_peer);
+        registry.checkin(&old_peer, idle).await;
+
+        assert!(registry.take_idle(&new_peer).is_none());
+        registry.checkin(&old_peer, in_use).await;
+        assert!(registry.take_idle(&new_peer).is_none());
+        assert!(registry.take_idle(&old_peer).is_none());
+    }
+}
@@ -1,35 +1,33 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fmt::{Debug, Write},
+    collections::HashMap,
+    fmt::{Debug, Display},
     fs::File,
     io,
-    sync::Arc,
+    sync::{Arc, Weak},
     time::Duration,
 };
 
-use analyzer::{PeerDDL, QueryAssociation};
+use analyzer::{MirrorAlteration, PeerDDL, QueryAssociation};
 use async_trait::async_trait;
-use bytes::{BufMut, BytesMut};
-use catalog::{Catalog, CatalogConfig, kms_decrypt};
+use auth::{CatalogAuthSource, ClientCertAuth, ClientCertStartupHandler};
+use cancel::{CancelKeyStartupHandler, CancelKeys};
+use catalog::{Catalog, CatalogConfig, CatalogExecutor, kms_decrypt};
 use clap::Parser;
 use cursor::PeerCursors;
-use dashmap::{DashMap, mapref::entry::Entry as DashEntry};
+use dashmap::DashMap;
+use executors::ExecutorRegistry;
 use flow_rs::grpc::{FlowGrpcClient, PeerCreationResult};
 use futures::Sink;
-use peer_connections::{PeerConnectionTracker, PeerConnections};
+use peer_connections::PeerConnections;
 use peer_cursor::{
     QueryExecutor, QueryOutput, Schema,
-    util::{records_to_query_response, sendable_stream_to_query_response},
+    util::{collect_records, records_to_query_response, sendable_stream_to_query_response},
 };
 use peerdb_parser::{NexusParsedStatement, NexusQueryParser, NexusStatement};
 use pgwire::{
     api::{
-        ClientInfo, ClientPortalStore, PgWireServerHandlers, Type,
-        auth::{
-            StartupHandler,
-            AuthSource, LoginInfo, Password, ServerParameterProvider,
-            scram::{SASLScramAuthStartupHandler, gen_salted_password},
-        },
+        ClientInfo, ClientPortalStore, METADATA_USER, PgWireServerHandlers, Type,
+        auth::{ServerParameterProvider, StartupHandler, scram::SASLScramAuthStartupHandler},
         portal::Portal,
         query::{ExtendedQueryHandler, SimpleQueryHandler},
         results::{
@@ -40,86 +38,119 @@ use pgwire::{
     },
     error::{ErrorInfo, PgWireError, PgWireResult},
     messages::PgWireBackendMessage,
-    tokio::process_socket,
 };
 use pt::{
     flow_model::QRepFlowJob,
     peerdb_peers::{Peer, peer::Config},
+    peerdb_route::{MaintenanceStatus, mirror_status_response::Status as MirrorStatus},
 };
-use rand::Rng;
 use rustls_pemfile::{certs, pkcs8_private_keys};
 use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use sqlparser::ast::Statement;
+use tokio::net::TcpListener;
 use tokio::signal::unix::{SignalKind, signal};
-use tokio::sync::Mutex;
-use tokio::{io::AsyncWriteExt, net::TcpListener};
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinSet;
 use tokio_rustls::TlsAcceptor;
-use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig, server::WebPkiClientVerifier};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use value::Value;
 
+mod auth;
+mod cancel;
 mod cursor;
-
-pub struct FixedPasswordAuthSource {
-    password: String,
-}
-
-impl FixedPasswordAuthSource {
-    pub fn new(password: String) -> Self {
-        Self { password }
-    }
-}
-
-#[async_trait]
-impl AuthSource for FixedPasswordAuthSource {
-    async fn get_password(&self, login_info: &LoginInfo) -> PgWireResult<Password> {
-        tracing::info!("login info: {:?}", login_info);
-
-        // randomly generate a 4 byte salt
-        let salt = rand::rng().random::<[u8; 4]>();
-        let hash_password = gen_salted_password(&self.password, &salt, 4096);
-        Ok(Password::new(Some(salt.to_vec()), hash_password))
-    }
+mod executors;
+mod session;
+mod show;
+mod validate;
+
+// a transaction block is pinned to the connection of the first peer it touches
+enum TransactionState {
+    // BEGIN was issued, it is sent to the peer along with its first statement
+    Pending {
+        begin: Statement,
+    },
+    Pinned {
+        peer_name: String,
+        executor: Arc<dyn QueryExecutor>,
+        // a statement failed, the peer rolls the transaction back on COMMIT
+        failed: bool,
+    },
 }
 
 pub struct NexusBackend {
     catalog: Arc<Catalog>,
-    peer_connections: PeerConnectionTracker,
+    catalog_executor: Arc<CatalogExecutor>,
     query_parser: NexusQueryParser,
     peer_cursors: Mutex<PeerCursors>,
-    executors: DashMap<String, Arc<dyn QueryExecutor>>,
+    peer_executors: Arc<ExecutorRegistry>,
+    // executors checked out by this session, with the peer they were created for
+    executors: DashMap<String, (Peer, Arc<dyn QueryExecutor>)>,
+    // the executor the current statement of the session runs on, cancel requests go to it
+    running: Mutex<Option<Weak<dyn QueryExecutor>>>,
+    transaction: Mutex<Option<TransactionState>>,
     flow_handler: Option<Arc<Mutex<FlowGrpcClient>>>,
     peerdb_fdw_mode: bool,
+    admin_user: String,
 }
 
 impl NexusBackend {
     pub fn new(
         catalog: Arc<Catalog>,
-        peer_connections: PeerConnectionTracker,
+        peer_executors: Arc<ExecutorRegistry>,
         flow_handler: Option<Arc<Mutex<FlowGrpcClient>>>,
         peerdb_fdw_mode: bool,
+        admin_user: String,
     ) -> Self {
         let query_parser = NexusQueryParser::new(catalog.clone());
         Self {
+            catalog_executor: Arc::new(CatalogExecutor::new(catalog.clone())),
             catalog,
-            peer_connections,
             query_parser,
             peer_cursors: Mutex::new(PeerCursors::new()),
+            peer_executors,
             executors: DashMap::new(),
+            running: Mutex::new(None),
+            transaction: Mutex::new(None),
             flow_handler,
             peerdb_fdw_mode,
+            admin_user,
+        }
+    }
+
+    // a cancel request only goes to the executor running the current statement,
+    // statements handled by nexus itself are not cancelled.
+    async fn cancel(&self) {
+        let running = self.running.lock().await.as_ref().and_then(Weak::upgrade);
+        let Some(executor) = running else {
+            return;
+        };
+        if let Err(err) = executor.cancel().await {
+            tracing::warn!("unable to cancel query: {}", err);
         }
     }
 
-    // execute a statement on a peer
+    async fn set_running(&self, executor: Option<&Arc<dyn QueryExecutor>>) {
+        *self.running.lock().await = executor.map(Arc::downgrade);
+    }
+
+    // execute a statement on a peer, `buffer_rows` reads a streamed result up front
     async fn process_execution<'a>(
         &self,
         result: QueryOutput,
         peer_holder: Option<Box<Peer>>,
+        buffer_rows: bool,
     ) -> PgWireResult<Vec<Response<'a>>> {
         match result {
             QueryOutput::AffectedRows(rows) => {
                 Ok(vec![Response::Execution(Tag::new("OK").with_rows(rows))])
             }
+            QueryOutput::Stream(rows) if buffer_rows => {
+                let records = collect_records(rows).await?;
+                let res = records_to_query_response(records)?;
+                Ok(vec![res])
+            }
             QueryOutput::Stream(rows) => {
                 let schema = rows.schema();
                 let res = sendable_stream_to_query_response(schema, rows)?;
@@ -175,7 +206,7 @@ impl NexusBackend {
         }
     }
 
-    async fn create_peer(&self, peer: &Peer) -> anyhow::Result<()> {
+    async fn create_peer(&self, peer: &Peer, allow_update: bool) -> anyhow::Result<()> {
         let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
 
         let create_request = pt::peerdb_route::CreatePeerRequest {
@@ -184,7 +215,7 @@ impl NexusBackend {
                 r#type: peer.r#type,
                 config: peer.config.clone(),
             }),
-            allow_update: false,
+            allow_update,
         };
 
         let create_response = flow_handler
@@ -193,6 +224,7 @@ impl NexusBackend {
             .map_err(|err| {
                 PgWireError::ApiError(format!("unable to check peer validity: {err:?}").into())
             })?;
+        self.catalog.invalidate_peers();
         if let PeerCreationResult::Failed(create_err) = create_response {
             Err(PgWireError::UserError(Box::new(ErrorInfo::new(
                 "ERROR".to_owned(),
@@ -210,7 +242,7 @@ impl NexusBackend {
         drop_mirror_stmt: &NexusStatement,
     ) -> PgWireResult<Vec<Response<'a>>> {
         match drop_mirror_stmt {
-            NexusStatement::PeerDDL { stmt: _, ddl } => match ddl.as_ref() {
+            NexusStatement::PeerDDL { ddl } => match ddl.as_ref() {
                 PeerDDL::DropMirror {
                     if_exists,
                     flow_job_name,
@@ -255,7 +287,7 @@ impl NexusBackend {
         create_mirror_stmt: &NexusStatement,
     ) -> PgWireResult<Vec<Response<'a>>> {
         match create_mirror_stmt {
-            NexusStatement::PeerDDL { stmt: _, ddl } => match ddl.as_ref() {
+            NexusStatement::PeerDDL { ddl } => match ddl.as_ref() {
                 PeerDDL::CreateMirrorForSelect {
                     if_not_exists,
                     qrep_flow_job,
@@ -299,23 +331,132 @@ impl NexusBackend {
         }
     }
 
+    fn check_manages_users<C: ClientInfo>(
+        &self,
+        client: &C,
+        nexus_stmt: &NexusStatement,
+    ) -> PgWireResult<()> {
+        let NexusStatement::PeerDDL { ddl } = nexus_stmt else {
+            return Ok(());
+        };
+        let user = client.metadata().get(METADATA_USER);
+        if !ddl.manages_users() || user == Some(&self.admin_user) {
+            return Ok(());
+        }
+        Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+            "ERROR".to_owned(),
+            "42501".to_owned(),
+            format!("only {} may manage users", self.admin_user),
+        ))))
+    }
+
     async fn handle_query<'a>(
         &self,
         nexus_stmt: NexusStatement,
+        buffer_rows: bool,
     ) -> PgWireResult<Vec<Response<'a>>> {
+        self.set_running(None).await;
+        if matches!(&nexus_stmt, NexusStatement::PeerDDL { ddl } if ddl.changes_mirrors()) {
+            self.check_not_in_maintenance().await?;
+        }
+
         match nexus_stmt {
-            NexusStatement::PeerDDL { stmt: _, ref ddl } => match ddl.as_ref() {
-                PeerDDL::CreatePeer { peer, .. } => {
-                    self.create_peer(peer).await.map_err(|e| {
+            NexusStatement::PeerDDL { ref ddl } => match ddl.as_ref() {
+                PeerDDL::CreatePeer {
+                    peer,
+                    if_not_exists,
+                    or_replace,
+                } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    if *if_not_exists {
+                        let peer_exists =
+                            self.catalog
+                                .check_peer_entry(&peer.name)
+                                .await
+                                .map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!(
+                                            "unable to query catalog for peer metadata: {err:?}"
+                                        )
+                                        .into(),
+                                    )
+                                })?;
+                        if peer_exists != 0 {
+                            let existing_peer_success = "PEER ALREADY EXISTS";
+                            return Ok(vec![Response::Execution(Tag::new(existing_peer_success))]);
+                        }
+                    }
+
+                    self.create_peer(peer, *or_replace).await.map_err(|e| {
                         PgWireError::UserError(Box::new(ErrorInfo::new(
                             "ERROR".to_owned(),
                             "internal_error".to_owned(),
                             e.to_string(),
                         )))
                     })?;
+                    if *or_replace {
+                        self.peer_executors.invalidate(&peer.name);
+                        self.executors.remove(&peer.name);
+                    }
 
                     Ok(vec![Response::Execution(Tag::new("OK"))])
                 }
+                PeerDDL::AlterPeer { peer_name, options } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let peer_exists =
+                        self.catalog
+                            .check_peer_entry(peer_name)
+                            .await
+                            .map_err(|err| {
+                                PgWireError::ApiError(
+                                    format!("unable to query catalog for peer metadata: {err:?}")
+                                        .into(),
+                                )
+                            })?;
+                    if peer_exists == 0 {
+                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("no such peer: {peer_name:?}"),
+                        ))));
+                    }
+
+                    let peer = self.catalog.get_peer(peer_name).await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to get peer config: {err:?}").into())
+                    })?;
+                    let peer = analyzer::merge_peer_options(&peer, options).map_err(|err| {
+                        PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "22023".to_owned(),
+                            format!("{err:#}"),
+                        )))
+                    })?;
+
+                    tracing::info!("ALTER PEER: peer_name: {}", peer_name);
+                    // the flow service validates the merged config before replacing the peer
+                    self.create_peer(&peer, true).await.map_err(|e| {
+                        PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "internal_error".to_owned(),
+                            e.to_string(),
+                        )))
+                    })?;
+                    self.peer_executors.invalidate(peer_name);
+                    self.executors.remove(peer_name);
+
+                    let alter_peer_success = format!("ALTER PEER {peer_name}");
+                    Ok(vec![Response::Execution(Tag::new(&alter_peer_success))])
+                }
                 PeerDDL::CreateMirrorForCDC {
                     if_not_exists,
                     flow_job,
@@ -329,34 +470,44 @@ impl NexusBackend {
                         Self::check_for_mirror(self.catalog.as_ref(), &flow_job.name).await?;
                     if !mirror_exists {
                         // reject duplicate source tables or duplicate target tables
-                        let table_mappings_count = flow_job.table_mappings.len();
-                        if table_mappings_count > 1 {
-                            let mut sources = HashSet::with_capacity(table_mappings_count);
-                            let mut destinations = HashSet::with_capacity(table_mappings_count);
-                            for tm in flow_job.table_mappings.iter() {
-                                if !sources.insert(tm.source_table_identifier.as_str()) {
-                                    return Err(PgWireError::ApiError(
-                                        format!(
-                                            "Duplicate source table identifier {}",
-                                            tm.source_table_identifier
-                                        )
-                                        .into(),
-                                    ));
-                                }
-                                if !destinations.insert(tm.destination_table_identifier.as_str()) {
-                                    return Err(PgWireError::ApiError(
-                                        format!(
-                                            "Duplicate destination table identifier {}",
-                                            tm.destination_table_identifier
-                                        )
-                                        .into(),
-                                    ));
-                                }
-                            }
+                        let tables = validate::mapped_tables(&flow_job.table_mappings);
+                        if let Some(problem) = validate::duplicate_tables(tables).into_iter().next()
+                        {
+                            return Err(PgWireError::ApiError(problem.into()));
                         }
 
                         // make a request to the flow service to start the job.
                         let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        // a missing script would only fail once the mirror starts syncing
+                        let script_problem =
+                            validate::script_problem(&mut flow_handler, &flow_job.script)
+                                .await
+                                .map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to list scripts: {err:?}").into(),
+                                    )
+                                })?;
+                        if let Some(problem) = script_problem {
+                            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                                "ERROR".to_owned(),
+                                "42704".to_owned(),
+                                problem,
+                            ))));
+                        }
+                        let env_problems = validate::env_problems(&mut flow_handler, &flow_job.env)
+                            .await
+                            .map_err(|err| {
+                                PgWireError::ApiError(
+                                    format!("unable to list settings: {err:?}").into(),
+                                )
+                            })?;
+                        if let Some(problem) = env_problems.into_iter().next() {
+                            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                                "ERROR".to_owned(),
+                                "22023".to_owned(),
+                                problem,
+                            ))));
+                        }
                         flow_handler
                             .start_peer_flow_job(
                                 flow_job,
@@ -369,6 +520,16 @@ impl NexusBackend {
                                     format!("unable to submit job: {:?}", err.to_string()).into(),
                                 )
                             })?;
+                        drop(flow_handler);
+                        // the mirror is running by now, failing the statement would hide that
+                        let tagged = if flow_job.tags.is_empty() {
+                            Ok(())
+                        } else {
+                            self.tag_mirror(&flow_job.name, &flow_job.tags).await
+                        };
+                        if let Err(err) = tagged {
+                            tracing::warn!("unable to tag mirror {}: {:?}", flow_job.name, err);
+                        }
 
                         let create_mirror_success = format!("CREATE MIRROR {}", flow_job.name);
                         Ok(vec![Response::Execution(Tag::new(&create_mirror_success))])
@@ -413,13 +574,13 @@ impl NexusBackend {
                             format!("unable to get peer config: {err:?}").into(),
                         )
                     })?;
-                    let executor = self.get_peer_executor(&peer).await.map_err(|err| {
-                        PgWireError::ApiError(
-                            format!("unable to get peer executor: {err:?}").into(),
-                        )
-                    })?;
-                    let res = executor.execute_raw(query).await?;
-                    self.process_execution(res, Some(Box::new(peer))).await
+                    let (peer_holder, executor) = self
+                        .get_transaction_executor(query, QueryAssociation::Peer(Box::new(peer)))
+                        .await?;
+                    self.set_running(Some(&executor)).await;
+                    let res = executor.execute_raw(query).await;
+                    let res = self.track_transaction_failure(&executor, res).await?;
+                    self.process_execution(res, peer_holder, buffer_rows).await
                 }
                 PeerDDL::DropMirror { .. } => self.handle_drop_mirror(&nexus_stmt).await,
                 PeerDDL::DropPeer {
@@ -453,6 +614,9 @@ impl NexusBackend {
                         flow_handler.drop_peer(peer_name).await.map_err(|err| {
                             PgWireError::ApiError(format!("unable to drop peer: {err:?}").into())
                         })?;
+                        self.catalog.invalidate_peers();
+                        self.peer_executors.invalidate(peer_name);
+                        self.executors.remove(peer_name);
                         let drop_peer_success = format!("DROP PEER {peer_name}");
                         Ok(vec![Response::Execution(Tag::new(&drop_peer_success))])
                     } else if *if_exists {
@@ -565,175 +729,1087 @@ impl NexusBackend {
                     let resume_mirror_success = format!("RESUME MIRROR {flow_job_name}");
                     Ok(vec![Response::Execution(Tag::new(&resume_mirror_success))])
                 }
-            },
-            NexusStatement::PeerQuery { stmt, assoc } => {
-                // get the query executor
-                let (peer_holder, executor): (Option<_>, Arc<dyn QueryExecutor>) = match assoc {
-                    QueryAssociation::Peer(peer) => {
-                        tracing::info!("handling peer[{}] query: {}", peer.name, stmt);
-                        (
-                            Some(peer.clone()),
-                            self.get_peer_executor(&peer).await.map_err(|err| {
-                                PgWireError::ApiError(
-                                    format!("unable to get peer executor: {err:?}").into(),
-                                )
-                            })?,
-                        )
-                    }
-                    QueryAssociation::Catalog => {
-                        tracing::info!("handling catalog query: {}", stmt);
-                        (None, self.catalog.clone())
-                    }
-                };
-
-                let res = executor.execute(&stmt).await?;
-                self.process_execution(res, peer_holder).await
-            }
-
-            NexusStatement::PeerCursor { stmt, cursor } => {
-                let executor = {
-                    let peer_cursors = self.peer_cursors.lock().await;
-                    let peer = match cursor {
-                        analyzer::CursorEvent::Fetch(c, _) => peer_cursors.get_peer(&c),
-                        analyzer::CursorEvent::CloseAll => todo!("close all cursors"),
-                        analyzer::CursorEvent::Close(c) => peer_cursors.get_peer(&c),
-                    };
-                    match peer {
-                        None => self.catalog.clone(),
-                        Some(peer) => self.get_peer_executor(peer).await.map_err(|err| {
-                            PgWireError::ApiError(
-                                format!("unable to get peer executor: {err:?}").into(),
-                            )
-                        })?,
+                PeerDDL::AlterMirror {
+                    flow_job_name,
+                    alteration,
+                } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
                     }
-                };
-
-                let res = executor.execute(&stmt).await?;
-                self.process_execution(res, None).await
-            }
-
-            NexusStatement::Rollback { stmt } => {
-                let res = self.catalog.execute(&stmt).await?;
-                self.process_execution(res, None).await
-            }
-
-            NexusStatement::Empty => Ok(vec![Response::EmptyQuery]),
-        }
-    }
-
-    async fn run_qrep_mirror(&self, qrep_flow_job: &QRepFlowJob) -> PgWireResult<String> {
-        // make a request to the flow service to start the job.
-        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
-        let workflow_id = flow_handler
-            .start_qrep_flow_job(
-                qrep_flow_job,
-                qrep_flow_job.source_peer.clone(),
-                qrep_flow_job.target_peer.clone(),
-            )
-            .await
-            .map_err(|err| {
-                PgWireError::ApiError(format!("unable to submit job: {err:?}").into())
-            })?;
-
-        self.catalog
-            .update_workflow_id_for_flow_job(&qrep_flow_job.name, &workflow_id)
-            .await
-            .map_err(|err| {
-                PgWireError::ApiError(
-                    format!("unable to update workflow for flow job: {err:?}").into(),
-                )
-            })?;
 
-        Ok(workflow_id)
-    }
+                    tracing::info!(
+                        "[ALTER MIRROR] mirror_name: {}, alteration: {:?}",
+                        flow_job_name,
+                        alteration
+                    );
 
-    async fn get_peer_executor(&self, peer: &Peer) -> anyhow::Result<Arc<dyn QueryExecutor>> {
-        Ok(match self.executors.entry(peer.name.clone()) {
-            DashEntry::Occupied(entry) => Arc::clone(entry.get()),
-            DashEntry::Vacant(entry) => {
-                let executor: Arc<dyn QueryExecutor> = match &peer.config {
-                    Some(Config::BigqueryConfig(c)) => {
-                        let executor = peer_bigquery::BigQueryQueryExecutor::new(
-                            peer.name.clone(),
-                            c,
-                            self.peer_connections.clone(),
-                        )
-                        .await?;
-                        Arc::new(executor)
-                    }
-                    Some(Config::MysqlConfig(c)) => {
-                        let executor =
-                            peer_mysql::MySqlQueryExecutor::new(peer.name.clone(), c).await?;
-                        Arc::new(executor)
-                    }
-                    Some(Config::PostgresConfig(c)) => {
-                        let executor =
-                            peer_postgres::PostgresQueryExecutor::new(peer.name.clone(), c).await?;
-                        Arc::new(executor)
-                    }
-                    Some(Config::SnowflakeConfig(c)) => {
-                        let executor = peer_snowflake::SnowflakeQueryExecutor::new(c).await?;
-                        Arc::new(executor)
+                    // tags are kept by the flow service, any mirror can be tagged in any state
+                    if let MirrorAlteration::SetTags(tags) = alteration {
+                        self.tag_mirror(flow_job_name, tags).await?;
+                        let alter_mirror_success = format!("ALTER MIRROR {flow_job_name}");
+                        return Ok(vec![Response::Execution(Tag::new(&alter_mirror_success))]);
                     }
-                    _ => {
-                        panic!("peer type not supported: {peer:?}")
-                    }
-                };
-
-                entry.insert(Arc::clone(&executor));
-                executor
-            }
-        })
-    }
 
-    async fn do_describe(&self, stmt: &NexusParsedStatement) -> PgWireResult<Option<Schema>> {
-        tracing::info!("[eqp] do_describe: {}", stmt.query);
-        let stmt = &stmt.statement;
-        match stmt {
-            NexusStatement::PeerDDL { .. } => Ok(None),
-            NexusStatement::PeerCursor { .. } => Ok(None),
-            NexusStatement::Empty => Ok(None),
-            NexusStatement::Rollback { .. } => Ok(None),
-            NexusStatement::PeerQuery { stmt, assoc } => {
-                let schema: Option<Schema> = match assoc {
-                    QueryAssociation::Peer(peer) => match &peer.config {
-                        Some(Config::BigqueryConfig(_)) => {
-                            let executor = self.get_peer_executor(peer).await.map_err(|err| {
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let status =
+                        flow_handler
+                            .mirror_status(flow_job_name)
+                            .await
+                            .map_err(|err| {
                                 PgWireError::ApiError(
-                                    format!("unable to get peer executor: {err:?}").into(),
+                                    format!("unable to get mirror status: {err:?}").into(),
                                 )
                             })?;
-                            executor.describe(stmt).await?
-                        }
-                        Some(Config::MysqlConfig(_)) => {
-                            let executor = self.get_peer_executor(peer).await.map_err(|err| {
-                                PgWireError::ApiError(
-                                    format!("unable to get peer executor: {err:?}").into(),
+                    if !matches!(status.status, Some(MirrorStatus::CdcStatus(_))) {
+                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("only CDC mirrors can be altered, {flow_job_name} is not one"),
+                        ))));
+                    }
+                    // the flow workflow applies config updates once the mirror is resumed
+                    if status.current_flow_state != pt::peerdb_flow::FlowStatus::StatusPaused as i32
+                    {
+                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!(
+                                "mirror {flow_job_name} must be paused before it is altered, \
+                                 run PAUSE MIRROR {flow_job_name} first and \
+                                 RESUME MIRROR {flow_job_name} after altering it"
+                            ),
+                        ))));
+                    }
+
+                    let config_update = match alteration {
+                        MirrorAlteration::AddTables(tables) => {
+                            // a table can only be mapped once, across the whole mirror
+                            let config = self.cdc_flow_config(flow_job_name).await?;
+                            let existing_tables = config.table_mappings.iter().map(|tm| {
+                                (
+                                    tm.source_table_identifier.as_str(),
+                                    tm.destination_table_identifier.as_str(),
                                 )
-                            })?;
-                            executor.describe(stmt).await?
+                            });
+                            let added_tables = validate::mapped_tables(tables);
+                            if let Some(problem) =
+                                validate::duplicate_tables(existing_tables.chain(added_tables))
+                                    .into_iter()
+                                    .next()
+                            {
+                                return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                                    "ERROR".to_owned(),
+                                    "error".to_owned(),
+                                    problem,
+                                ))));
+                            }
+
+                            let additional_tables = tables
+                                .iter()
+                                .map(flow_rs::grpc::table_mapping)
+                                .collect::<anyhow::Result<Vec<_>>>()
+                                .map_err(|err| {
+                                    PgWireError::UserError(Box::new(ErrorInfo::new(
+                                        "ERROR".to_owned(),
+                                        "error".to_owned(),
+                                        err.to_string(),
+                                    )))
+                                })?;
+                            pt::peerdb_flow::CdcFlowConfigUpdate {
+                                additional_tables,
+                                ..Default::default()
+                            }
                         }
-                        Some(Config::PostgresConfig(_)) => {
-                            let executor = self.get_peer_executor(peer).await.map_err(|err| {
-                                PgWireError::ApiError(
-                                    format!("unable to get peer executor: {err:?}").into(),
-                                )
-                            })?;
-                            executor.describe(stmt).await?
+                        MirrorAlteration::RemoveTables(sources) => {
+                            let config = self.cdc_flow_config(flow_job_name).await?;
+                            let removed_tables = sources
+                                .iter()
+                                .map(|source| {
+                                    config
+                                        .table_mappings
+                                        .iter()
+                                        .find(|tm| tm.source_table_identifier == *source)
+                                        .cloned()
+                                        .ok_or_else(|| {
+                                            PgWireError::UserError(Box::new(ErrorInfo::new(
+                                                "ERROR".to_owned(),
+                                                "error".to_owned(),
+                                                format!("table {source} is not in the mirror"),
+                                            )))
+                                        })
+                                })
+                                .collect::<PgWireResult<Vec<_>>>()?;
+                            pt::peerdb_flow::CdcFlowConfigUpdate {
+                                removed_tables,
+                                ..Default::default()
+                            }
                         }
-                        Some(Config::SnowflakeConfig(_)) => {
-                            let executor = self.get_peer_executor(peer).await.map_err(|err| {
+                        MirrorAlteration::Set(config_update) => config_update.as_ref().clone(),
+                        MirrorAlteration::SetTags(_) => unreachable!(),
+                    };
+
+                    flow_handler
+                        .flow_state_change(
+                            flow_job_name,
+                            // the mirror stays paused, RESUME MIRROR starts it with the update
+                            pt::peerdb_flow::FlowStatus::StatusPaused,
+                            Some(pt::peerdb_flow::FlowConfigUpdate {
+                                update: Some(
+                                    pt::peerdb_flow::flow_config_update::Update::CdcFlowConfigUpdate(
+                                        config_update,
+                                    ),
+                                ),
+                            }),
+                        )
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(format!("unable to alter mirror: {err:?}").into())
+                        })?;
+
+                    let alter_mirror_success = format!("ALTER MIRROR {flow_job_name}");
+                    Ok(vec![Response::Execution(Tag::new(&alter_mirror_success))])
+                }
+                PeerDDL::CreateUser {
+                    if_not_exists,
+                    user_name,
+                    password,
+                } => {
+                    tracing::info!(
+                        "CREATE USER: user_name: {}, if_not_exists: {}",
+                        user_name,
+                        if_not_exists
+                    );
+                    let user = auth::new_user(user_name.clone(), password);
+                    let created = self.catalog.create_user(&user).await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to create user: {err:?}").into())
+                    })?;
+                    if created {
+                        let create_user_success = format!("CREATE USER {user_name}");
+                        Ok(vec![Response::Execution(Tag::new(&create_user_success))])
+                    } else if *if_not_exists {
+                        let existing_user_success = "USER ALREADY EXISTS";
+                        Ok(vec![Response::Execution(Tag::new(existing_user_success))])
+                    } else {
+                        Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("user already exists: {user_name:?}"),
+                        ))))
+                    }
+                }
+                PeerDDL::AlterUser {
+                    user_name,
+                    password,
+                } => {
+                    tracing::info!("ALTER USER: user_name: {}", user_name);
+                    let user = auth::new_user(user_name.clone(), password);
+                    let updated =
+                        self.catalog
+                            .update_user_password(&user)
+                            .await
+                            .map_err(|err| {
                                 PgWireError::ApiError(
-                                    format!("unable to get peer executor: {err:?}").into(),
+                                    format!("unable to alter user: {err:?}").into(),
                                 )
                             })?;
+                    if updated {
+                        let alter_user_success = format!("ALTER USER {user_name}");
+                        Ok(vec![Response::Execution(Tag::new(&alter_user_success))])
+                    } else {
+                        Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("no such user: {user_name:?}"),
+                        ))))
+                    }
+                }
+                PeerDDL::DropUser {
+                    if_exists,
+                    user_name,
+                } => {
+                    tracing::info!(
+                        "DROP USER: user_name: {}, if_exists: {}",
+                        user_name,
+                        if_exists
+                    );
+                    let dropped = self.catalog.drop_user(user_name).await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to drop user: {err:?}").into())
+                    })?;
+                    if dropped {
+                        let drop_user_success = format!("DROP USER {user_name}");
+                        Ok(vec![Response::Execution(Tag::new(&drop_user_success))])
+                    } else if *if_exists {
+                        let no_user_success = "NO SUCH USER";
+                        Ok(vec![Response::Execution(Tag::new(no_user_success))])
+                    } else {
+                        Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("no such user: {user_name:?}"),
+                        ))))
+                    }
+                }
+                PeerDDL::ShowPeers => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let items = {
+                        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        flow_handler.list_peers().await.map_err(|err| {
+                            PgWireError::ApiError(format!("unable to list peers: {err:?}").into())
+                        })?
+                    };
+                    let peers = self.catalog.get_cached_peers().await.map_err(|err| {
+                        PgWireError::ApiError(
+                            format!("unable to query catalog for peers: {err:?}").into(),
+                        )
+                    })?;
+                    let records = show::peers_records(items, &peers).map_err(|err| {
+                        PgWireError::ApiError(format!("unable to show peers: {err:?}").into())
+                    })?;
+                    Ok(vec![records_to_query_response(records)?])
+                }
+                PeerDDL::ShowMirrors { tags } => {
+                    let Some(flow_handler) = self.flow_handler.as_ref() else {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    };
+
+                    let items = flow_handler
+                        .lock()
+                        .await
+                        .list_mirrors()
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(format!("unable to list mirrors: {err:?}").into())
+                        })?;
+                    let mut flow_tags = self.catalog.get_flow_tags().await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to get mirror tags: {err:?}").into())
+                    })?;
+                    let mut mirrors = Vec::with_capacity(items.len());
+                    for mirror in items {
+                        let mirror_tags = flow_tags.remove(&mirror.name).unwrap_or_default();
+                        if tags
+                            .iter()
+                            .any(|(key, value)| mirror_tags.get(key) != Some(value))
+                        {
+                            continue;
+                        }
+                        // other sessions get the flow service between the status requests
+                        let status = flow_handler.lock().await.mirror_status(&mirror.name).await;
+                        let status = match status {
+                            Ok(status) => {
+                                pt::peerdb_flow::FlowStatus::try_from(status.current_flow_state)
+                                    .ok()
+                            }
+                            Err(err) => {
+                                tracing::warn!(
+                                    "unable to get status of mirror {}: {:?}",
+                                    mirror.name,
+                                    err
+                                );
+                                None
+                            }
+                        };
+                        mirrors.push((mirror, mirror_tags, status));
+                    }
+                    Ok(vec![records_to_query_response(show::mirrors_records(
+                        mirrors,
+                    ))?])
+                }
+                PeerDDL::ExplainMirror { create_mirror } => {
+                    let records = show::mirror_config_records(create_mirror).map_err(|err| {
+                        PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "internal_error".to_owned(),
+                            format!("{err:#}"),
+                        )))
+                    })?;
+                    Ok(vec![records_to_query_response(records)?])
+                }
+                PeerDDL::CreateAlert { config } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let id = flow_handler
+                        .post_alert_config(config.as_ref().clone())
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(format!("unable to create alert: {err:?}").into())
+                        })?;
+
+                    // the id is needed to drop the alert later
+                    let create_alert_success = format!("CREATE ALERT {id}");
+                    Ok(vec![Response::Execution(Tag::new(&create_alert_success))])
+                }
+                PeerDDL::DropAlert { id } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    flow_handler.delete_alert_config(*id).await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to drop alert: {err:?}").into())
+                    })?;
+
+                    let drop_alert_success = format!("DROP ALERT {id}");
+                    Ok(vec![Response::Execution(Tag::new(&drop_alert_success))])
+                }
+                PeerDDL::ShowAlerts => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let configs = {
+                        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        flow_handler.alert_configs().await.map_err(|err| {
+                            PgWireError::ApiError(format!("unable to list alerts: {err:?}").into())
+                        })?
+                    };
+                    let records = show::alerts_records(configs).map_err(|err| {
+                        PgWireError::ApiError(format!("unable to show alerts: {err:?}").into())
+                    })?;
+                    Ok(vec![records_to_query_response(records)?])
+                }
+                PeerDDL::ShowSettings => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let settings = {
+                        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        flow_handler.dynamic_settings().await.map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to list settings: {err:?}").into(),
+                            )
+                        })?
+                    };
+                    Ok(vec![records_to_query_response(show::settings_records(
+                        settings,
+                    ))?])
+                }
+                PeerDDL::AlterSetting { name, value } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let settings = flow_handler.dynamic_settings().await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to list settings: {err:?}").into())
+                    })?;
+                    let Some(setting) = settings.iter().find(|setting| setting.name == *name)
+                    else {
+                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "42704".to_owned(),
+                            format!("unrecognized configuration parameter {name}"),
+                        ))));
+                    };
+                    if let Some(problem) = validate::setting_problem(setting, value) {
+                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "22023".to_owned(),
+                            problem,
+                        ))));
+                    }
+                    flow_handler
+                        .post_dynamic_setting(name, value)
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(format!("unable to set {name}: {err:?}").into())
+                        })?;
+
+                    Ok(vec![Response::Execution(Tag::new("ALTER SYSTEM"))])
+                }
+                PeerDDL::CreateScript {
+                    or_replace,
+                    script_name,
+                    lang,
+                    source,
+                } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let scripts = flow_handler.scripts().await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to list scripts: {err:?}").into())
+                    })?;
+                    // an id of -1 creates the script, replacing keeps the id of the existing one
+                    let id = match scripts.iter().find(|script| script.name == *script_name) {
+                        Some(existing) if *or_replace => existing.id,
+                        Some(_) => {
+                            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                                "ERROR".to_owned(),
+                                "42710".to_owned(),
+                                format!("script {script_name} already exists"),
+                            ))));
+                        }
+                        None => -1,
+                    };
+                    flow_handler
+                        .post_script(pt::peerdb_route::Script {
+                            id,
+                            lang: lang.clone(),
+                            name: script_name.clone(),
+                            source: source.clone(),
+                        })
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to create script: {err:?}").into(),
+                            )
+                        })?;
+
+                    let create_script_success = format!("CREATE SCRIPT {script_name}");
+                    Ok(vec![Response::Execution(Tag::new(&create_script_success))])
+                }
+                PeerDDL::DropScript {
+                    if_exists,
+                    script_name,
+                } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let scripts = flow_handler.scripts().await.map_err(|err| {
+                        PgWireError::ApiError(format!("unable to list scripts: {err:?}").into())
+                    })?;
+                    match scripts.iter().find(|script| script.name == *script_name) {
+                        Some(script) => {
+                            flow_handler.delete_script(script.id).await.map_err(|err| {
+                                PgWireError::ApiError(
+                                    format!("unable to drop script: {err:?}").into(),
+                                )
+                            })?;
+                            let drop_script_success = format!("DROP SCRIPT {script_name}");
+                            Ok(vec![Response::Execution(Tag::new(&drop_script_success))])
+                        }
+                        None if *if_exists => {
+                            let no_script_success = "NO SUCH SCRIPT";
+                            Ok(vec![Response::Execution(Tag::new(no_script_success))])
+                        }
+                        None => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                            "ERROR".to_owned(),
+                            "error".to_owned(),
+                            format!("no such script: {script_name}"),
+                        )))),
+                    }
+                }
+                PeerDDL::ShowScripts => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let scripts = {
+                        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        flow_handler.scripts().await.map_err(|err| {
+                            PgWireError::ApiError(format!("unable to list scripts: {err:?}").into())
+                        })?
+                    };
+                    Ok(vec![records_to_query_response(show::scripts_records(
+                        scripts,
+                    ))?])
+                }
+                PeerDDL::EnterMaintenance | PeerDDL::ExitMaintenance => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let (status, tag) = if matches!(ddl.as_ref(), PeerDDL::EnterMaintenance) {
+                        (MaintenanceStatus::Start, "ENTER MAINTENANCE")
+                    } else {
+                        (MaintenanceStatus::End, "EXIT MAINTENANCE")
+                    };
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let workflow_id = flow_handler.maintenance(status).await.map_err(|err| {
+                        PgWireError::ApiError(
+                            format!("unable to start maintenance workflow: {err:?}").into(),
+                        )
+                    })?;
+                    // the workflow keeps running, SHOW MAINTENANCE STATUS follows its progress
+                    tracing::info!("[{}] started workflow {}", tag, workflow_id);
+                    Ok(vec![Response::Execution(Tag::new(tag))])
+                }
+                PeerDDL::ShowMaintenanceStatus => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let status = {
+                        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                        flow_handler.maintenance_status().await.map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to get maintenance status: {err:?}").into(),
+                            )
+                        })?
+                    };
+                    Ok(vec![records_to_query_response(show::maintenance_records(
+                        status,
+                    ))?])
+                }
+                PeerDDL::ValidatePeer { create_peer } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let problems = validate::validate_peer(&mut flow_handler, create_peer)
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to validate peer: {err:?}").into(),
+                            )
+                        })?;
+                    Ok(vec![records_to_query_response(show::problems_records(
+                        problems,
+                    ))?])
+                }
+                PeerDDL::ValidateMirror { create_mirror } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let problems =
+                        validate::validate_mirror(&self.catalog, &mut flow_handler, create_mirror)
+                            .await
+                            .map_err(|err| {
+                                PgWireError::ApiError(
+                                    format!("unable to validate mirror: {err:?}").into(),
+                                )
+                            })?;
+                    Ok(vec![records_to_query_response(show::problems_records(
+                        problems,
+                    ))?])
+                }
+                PeerDDL::DescribeMirror { flow_job_name } => {
+                    if self.flow_handler.is_none() {
+                        return Err(PgWireError::ApiError(
+                            "flow service is not configured".into(),
+                        ));
+                    }
+
+                    let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+                    let status =
+                        flow_handler
+                            .mirror_status(flow_job_name)
+                            .await
+                            .map_err(|err| {
+                                PgWireError::ApiError(
+                                    format!("unable to get mirror status: {err:?}").into(),
+                                )
+                            })?;
+                    let (summaries, batches) =
+                        if matches!(status.status, Some(MirrorStatus::CdcStatus(_))) {
+                            let summaries = flow_handler
+                                .initial_load_summary(flow_job_name)
+                                .await
+                                .map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get snapshot progress: {err:?}").into(),
+                                    )
+                                })?;
+                            let batches = flow_handler
+                                .cdc_batches(flow_job_name, show::RECENT_BATCHES)
+                                .await
+                                .map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get cdc batches: {err:?}").into(),
+                                    )
+                                })?;
+                            (summaries, batches)
+                        } else {
+                            (Vec::new(), Vec::new())
+                        };
+                    let last_error = flow_handler
+                        .last_mirror_error(flow_job_name)
+                        .await
+                        .map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to get mirror errors: {err:?}").into(),
+                            )
+                        })?;
+                    Ok(vec![records_to_query_response(
+                        show::mirror_status_records(status, summaries, batches, last_error),
+                    )?])
+                }
+            },
+            NexusStatement::PeerQuery { stmt, assoc } => {
+                let (peer_holder, executor) = self.get_transaction_executor(&stmt, assoc).await?;
+                self.set_running(Some(&executor)).await;
+                let res = executor.execute(&stmt).await;
+                let res = self.track_transaction_failure(&executor, res).await?;
+                self.process_execution(res, peer_holder, buffer_rows).await
+            }
+
+            NexusStatement::PeerCursor { stmt, cursor } => {
+                let executor = {
+                    let peer_cursors = self.peer_cursors.lock().await;
+                    let peer = match cursor {
+                        analyzer::CursorEvent::Fetch(c, _) => peer_cursors.get_peer(&c),
+                        analyzer::CursorEvent::CloseAll => todo!("close all cursors"),
+                        analyzer::CursorEvent::Close(c) => peer_cursors.get_peer(&c),
+                    };
+                    match peer {
+                        None => self.catalog_executor.clone(),
+                        Some(peer) => self.get_session_executor(peer).await.map_err(|err| {
+                            PgWireError::ApiError(
+                                format!("unable to get peer executor: {err:?}").into(),
+                            )
+                        })?,
+                    }
+                };
+
+                self.set_running(Some(&executor)).await;
+                let res = executor.execute(&stmt).await?;
+                self.process_execution(res, None, buffer_rows).await
+            }
+
+            NexusStatement::Transaction { stmt } => self.handle_transaction(stmt).await,
+
+            NexusStatement::Empty => Ok(vec![Response::EmptyQuery]),
+        }
+    }
+
+    // statements with parameters are only bound natively for peer queries,
+    // nexus statements carry no parameters.
+    async fn handle_query_with_params<'a>(
+        &self,
+        nexus_stmt: NexusStatement,
+        params: &[Value],
+    ) -> PgWireResult<Vec<Response<'a>>> {
+        match nexus_stmt {
+            NexusStatement::PeerQuery { stmt, assoc } => {
+                let (peer_holder, executor) = self.get_transaction_executor(&stmt, assoc).await?;
+                self.set_running(Some(&executor)).await;
+                let res = executor.execute_with_params(&stmt, params).await;
+                let res = self.track_transaction_failure(&executor, res).await?;
+                self.process_execution(res, peer_holder, false).await
+            }
+            nexus_stmt if params.is_empty() => self.handle_query(nexus_stmt, false).await,
+            _ => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "0A000".to_owned(),
+                "parameters are only supported for peer queries".to_owned(),
+            )))),
+        }
+    }
+
+    async fn handle_transaction<'a>(&self, stmt: Statement) -> PgWireResult<Vec<Response<'a>>> {
+        let mut transaction = self.transaction.lock().await;
+        match stmt {
+            Statement::StartTransaction { .. } => {
+                if transaction.is_none() {
+                    *transaction = Some(TransactionState::Pending { begin: stmt });
+                } else {
+                    tracing::warn!("there is already a transaction in progress");
+                }
+                Ok(vec![Response::TransactionStart(Tag::new("BEGIN"))])
+            }
+            Statement::Commit { .. }
+            | Statement::Rollback {
+                savepoint: None, ..
+            } => {
+                let mut tag = if matches!(stmt, Statement::Commit { .. }) {
+                    "COMMIT"
+                } else {
+                    "ROLLBACK"
+                };
+                if let Some(TransactionState::Pinned {
+                    peer_name,
+                    executor,
+                    failed,
+                }) = transaction.take()
+                {
+                    if failed {
+                        tag = "ROLLBACK";
+                    }
+                    tracing::info!("{} transaction on peer {}", tag, peer_name);
+                    self.set_running(Some(&executor)).await;
+                    executor.execute(&stmt).await?;
+                }
+                Ok(vec![Response::TransactionEnd(Tag::new(tag))])
+            }
+            // savepoints live on the pinned connection
+            _ => match &mut *transaction {
+                Some(TransactionState::Pinned {
+                    executor, failed, ..
+                }) => {
+                    self.set_running(Some(&*executor)).await;
+                    let res = executor.execute(&stmt).await;
+                    // rolling back to a savepoint recovers from the failure
+                    *failed =
+                        res.is_err() || (*failed && !matches!(stmt, Statement::Rollback { .. }));
+                    res?;
+                    let tag = match stmt {
+                        Statement::Savepoint { .. } => "SAVEPOINT",
+                        Statement::ReleaseSavepoint { .. } => "RELEASE",
+                        _ => "ROLLBACK",
+                    };
+                    Ok(vec![Response::Execution(Tag::new(tag))])
+                }
+                Some(TransactionState::Pending { .. }) => {
+                    Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                        "ERROR".to_owned(),
+                        "25000".to_owned(),
+                        format!("{stmt} must follow a statement on a peer in the transaction"),
+                    ))))
+                }
+                None => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "25P01".to_owned(),
+                    format!("{stmt} can only be used in transaction blocks"),
+                )))),
+            },
+        }
+    }
+
+    // inside a transaction block statements run on the pinned peer connection,
+    // the first statement on a peer sends the BEGIN along and pins it.
+    async fn get_transaction_executor(
+        &self,
+        stmt: &(impl Display + Sync),
+        assoc: QueryAssociation,
+    ) -> PgWireResult<(Option<Box<Peer>>, Arc<dyn QueryExecutor>)> {
+        let mut transaction = self.transaction.lock().await;
+        match (&*transaction, &assoc) {
+            (None, _) => self.get_query_executor(stmt, assoc).await,
+            (Some(TransactionState::Pending { .. }), QueryAssociation::Catalog) => {
+                Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "25000".to_owned(),
+                    "statements on the catalog cannot run inside a transaction block".to_owned(),
+                ))))
+            }
+            (Some(TransactionState::Pending { begin }), QueryAssociation::Peer(peer)) => {
+                if !matches!(peer.config, Some(Config::PostgresConfig(_))) {
+                    return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                        "ERROR".to_owned(),
+                        "0A000".to_owned(),
+                        format!(
+                            "transactions are only supported on postgres peers, {} is not one",
+                            peer.name
+                        ),
+                    ))));
+                }
+                let begin = begin.clone();
+                let peer_name = peer.name.clone();
+                let (peer_holder, executor) = self.get_query_executor(stmt, assoc).await?;
+                executor.execute(&begin).await?;
+                tracing::info!("transaction pinned to peer {}", peer_name);
+                *transaction = Some(TransactionState::Pinned {
+                    peer_name,
+                    executor: executor.clone(),
+                    failed: false,
+                });
+                Ok((peer_holder, executor))
+            }
+            (
+                Some(TransactionState::Pinned {
+                    peer_name,
+                    executor,
+                    ..
+                }),
+                QueryAssociation::Peer(peer),
+            ) if *peer_name == peer.name => {
+                tracing::info!(
+                    "handling peer[{}] query in transaction: {}",
+                    peer_name,
+                    stmt
+                );
+                Ok((Some(peer.clone()), executor.clone()))
+            }
+            (Some(TransactionState::Pinned { peer_name, .. }), _) => {
+                let target = match &assoc {
+                    QueryAssociation::Peer(peer) => format!("peer {}", peer.name),
+                    QueryAssociation::Catalog => "the catalog".to_owned(),
+                };
+                Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "25000".to_owned(),
+                    format!(
+                        "current transaction is pinned to peer {peer_name}, \
+                        cannot run statements on {target} until it is committed or rolled back"
+                    ),
+                ))))
+            }
+        }
+    }
+
+    // statements failing on the connection of the transaction abort it
+    async fn track_transaction_failure<T>(
+        &self,
+        executor: &Arc<dyn QueryExecutor>,
+        res: PgWireResult<T>,
+    ) -> PgWireResult<T> {
+        if let Some(TransactionState::Pinned {
+            executor: pinned,
+            failed,
+            ..
+        }) = &mut *self.transaction.lock().await
+        {
+            *failed |= res.is_err() && Arc::ptr_eq(pinned, executor);
+        }
+        res
+    }
+
+    async fn get_query_executor(
+        &self,
+        stmt: &(impl Display + Sync),
+        assoc: QueryAssociation,
+    ) -> PgWireResult<(Option<Box<Peer>>, Arc<dyn QueryExecutor>)> {
+        match assoc {
+            QueryAssociation::Peer(peer) => {
+                tracing::info!("handling peer[{}] query: {}", peer.name, stmt);
+                let executor = self.get_session_executor(&peer).await.map_err(|err| {
+                    PgWireError::ApiError(format!("unable to get peer executor: {err:?}").into())
+                })?;
+                Ok((Some(peer), executor))
+            }
+            QueryAssociation::Catalog => {
+                tracing::info!("handling catalog query: {}", stmt);
+                Ok((None, self.catalog_executor.clone()))
+            }
+        }
+    }
+
+    async fn run_qrep_mirror(&self, qrep_flow_job: &QRepFlowJob) -> PgWireResult<String> {
+        // make a request to the flow service to start the job.
+        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+        let workflow_id = flow_handler
+            .start_qrep_flow_job(
+                qrep_flow_job,
+                qrep_flow_job.source_peer.clone(),
+                qrep_flow_job.target_peer.clone(),
+            )
+            .await
+            .map_err(|err| {
+                PgWireError::ApiError(format!("unable to submit job: {err:?}").into())
+            })?;
+
+        self.catalog
+            .update_workflow_id_for_flow_job(&qrep_flow_job.name, &workflow_id)
+            .await
+            .map_err(|err| {
+                PgWireError::ApiError(
+                    format!("unable to update workflow for flow job: {err:?}").into(),
+                )
+            })?;
+
+        Ok(workflow_id)
+    }
+
+    // upgrades pause all mirrors and resume them afterwards, they must not change meanwhile
+    async fn check_not_in_maintenance(&self) -> PgWireResult<()> {
+        let Some(flow_handler) = self.flow_handler.as_ref() else {
+            return Ok(());
+        };
+        let status = flow_handler
+            .lock()
+            .await
+            .maintenance_status()
+            .await
+            .map_err(|err| {
+                PgWireError::ApiError(format!("unable to get maintenance status: {err:?}").into())
+            })?;
+        if status.maintenance_running {
+            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "55000".to_owned(),
+                "mirrors cannot be changed during maintenance, run EXIT MAINTENANCE first"
+                    .to_owned(),
+            ))));
+        }
+        Ok(())
+    }
+
+    async fn cdc_flow_config(
+        &self,
+        flow_job_name: &str,
+    ) -> PgWireResult<pt::peerdb_flow::FlowConnectionConfigs> {
+        self.catalog
+            .get_cdc_flow_config(flow_job_name)
+            .await
+            .map_err(|err| {
+                PgWireError::ApiError(format!("unable to get mirror config: {err:?}").into())
+            })?
+            .ok_or_else(|| {
+                PgWireError::ApiError(format!("no config found for mirror {flow_job_name}").into())
+            })
+    }
+
+    async fn tag_mirror(
+        &self,
+        flow_job_name: &str,
+        tags: &HashMap<String, String>,
+    ) -> PgWireResult<()> {
+        let mut flow_handler = self.flow_handler.as_ref().unwrap().lock().await;
+        flow_handler
+            .create_or_replace_flow_tags(flow_job_name, tags)
+            .await
+            .map_err(|err| PgWireError::ApiError(format!("unable to tag mirror: {err:?}").into()))
+    }
+
+    // the session keeps the executor it checked out until it ends, unless the
+    // connection died or the peer was changed meanwhile.
+    async fn get_session_executor(&self, peer: &Peer) -> anyhow::Result<Arc<dyn QueryExecutor>> {
+        if let Some(entry) = self.executors.get(&peer.name) {
+            let (executor_peer, executor) = entry.value();
+            if executor_peer == peer && !executor.is_closed() {
+                return Ok(Arc::clone(executor));
+            }
+        }
+
+        let executor = self.peer_executors.checkout(peer).await?;
+        self.executors
+            .insert(peer.name.clone(), (peer.clone(), Arc::clone(&executor)));
+        Ok(executor)
+    }
+
+    /// Hand the executors of the session back to the pools once the session has ended.
+    pub async fn release_executors(&self) {
+        self.running.lock().await.take();
+        self.transaction.lock().await.take();
+        if let Err(err) = self.catalog_executor.reset().await {
+            tracing::warn!("dropping catalog connection: {}", err);
+        }
+        let peer_names: Vec<String> = self
+            .executors
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        for peer_name in peer_names {
+            if let Some((_, (peer, executor))) = self.executors.remove(&peer_name) {
+                self.peer_executors.checkin(&peer, executor).await;
+            }
+        }
+    }
+
+    // parameter types declared by the client are kept, the rest are inferred by the peer
+    async fn do_describe_params(
+        &self,
+        stmt: &NexusParsedStatement,
+        declared_types: &[Type],
+    ) -> PgWireResult<Vec<Type>> {
+        let NexusStatement::PeerQuery { stmt, assoc } = &stmt.statement else {
+            return Ok(declared_types.to_vec());
+        };
+
+        let mut param_types = declared_types.to_vec();
+        let param_count = peer_ast::placeholder_count(stmt).max(param_types.len());
+        param_types.resize(param_count, Type::UNKNOWN);
+        if param_types.contains(&Type::UNKNOWN) {
+            let (_, executor) = self.get_query_executor(stmt, assoc.clone()).await?;
+            if let Some(inferred_types) = executor.describe_params(stmt).await? {
+                for (param_type, inferred_type) in param_types.iter_mut().zip(inferred_types) {
+                    if *param_type == Type::UNKNOWN {
+                        *param_type = inferred_type;
+                    }
+                }
+            }
+        }
+        Ok(param_types)
+    }
+
+    async fn do_describe(&self, stmt: &NexusParsedStatement) -> PgWireResult<Option<Schema>> {
+        tracing::info!("[eqp] do_describe: {}", stmt.loggable_query());
+        let stmt = &stmt.statement;
+        match stmt {
+            NexusStatement::PeerDDL { ddl } => Ok(match ddl.as_ref() {
+                PeerDDL::ShowPeers => Some(show::peers_schema()),
+                PeerDDL::ShowMirrors { .. } => Some(show::mirrors_schema()),
+                PeerDDL::DescribeMirror { .. } => Some(show::mirror_status_schema()),
+                PeerDDL::ExplainMirror { .. } => Some(show::config_schema()),
+                PeerDDL::ShowAlerts => Some(show::alerts_schema()),
+                PeerDDL::ShowSettings => Some(show::settings_schema()),
+                PeerDDL::ShowScripts => Some(show::scripts_schema()),
+                PeerDDL::ShowMaintenanceStatus => Some(show::maintenance_schema()),
+                PeerDDL::ValidatePeer { .. } | PeerDDL::ValidateMirror { .. } => {
+                    Some(show::problems_schema())
+                }
+                _ => None,
+            }),
+            NexusStatement::PeerCursor { .. } => Ok(None),
+            NexusStatement::Empty => Ok(None),
+            NexusStatement::Transaction { .. } => Ok(None),
+            NexusStatement::PeerQuery { stmt, assoc } => {
+                let schema: Option<Schema> = match assoc {
+                    QueryAssociation::Peer(peer) => match &peer.config {
+                        Some(Config::BigqueryConfig(_)) => {
+                            let executor =
+                                self.get_session_executor(peer).await.map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get peer executor: {err:?}").into(),
+                                    )
+                                })?;
+                            executor.describe(stmt).await?
+                        }
+                        Some(Config::MysqlConfig(_)) => {
+                            let executor =
+                                self.get_session_executor(peer).await.map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get peer executor: {err:?}").into(),
+                                    )
+                                })?;
+                            executor.describe(stmt).await?
+                        }
+                        Some(Config::PostgresConfig(_)) => {
+                            let executor =
+                                self.get_session_executor(peer).await.map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get peer executor: {err:?}").into(),
+                                    )
+                                })?;
+                            executor.describe(stmt).await?
+                        }
+                        Some(Config::SnowflakeConfig(_)) => {
+                            let executor =
+                                self.get_session_executor(peer).await.map_err(|err| {
+                                    PgWireError::ApiError(
+                                        format!("unable to get peer executor: {err:?}").into(),
+                                    )
+                                })?;
                             executor.describe(stmt).await?
                         }
                         _ => {
                             panic!("peer type not supported: {peer:?}")
                         }
                     },
-                    QueryAssociation::Catalog => self.catalog.describe(stmt).await?,
+                    QueryAssociation::Catalog => self.catalog_executor.describe(stmt).await?,
                 };
 
                 Ok(if self.peerdb_fdw_mode { None } else { schema })
@@ -744,57 +1820,90 @@ impl NexusBackend {
 
 #[async_trait]
 impl SimpleQueryHandler for NexusBackend {
-    async fn do_query<'a, C>(&self, _client: &mut C, sql: &str) -> PgWireResult<Vec<Response<'a>>>
+    async fn do_query<'a, C>(&self, client: &mut C, sql: &str) -> PgWireResult<Vec<Response<'a>>>
     where
         C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
         C::Error: Debug,
         PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
     {
-        let parsed = self.query_parser.parse_simple_sql(sql).await?;
-        let nexus_stmt = parsed.statement;
-        self.handle_query(nexus_stmt).await
+        let stmts = self.query_parser.split_simple_sql(sql)?;
+        if stmts.is_empty() {
+            return Ok(vec![Response::EmptyQuery]);
+        }
+
+        // statements run one after another with a response each, the first
+        // failing statement ends the script like it would in postgres.
+        let stmt_count = stmts.len();
+        let mut responses = Vec::with_capacity(stmt_count);
+        for (idx, stmt) in stmts.into_iter().enumerate() {
+            let res = async {
+                let nexus_stmt = self.query_parser.analyze_simple_statement(stmt).await?;
+                self.check_manages_users(client, &nexus_stmt)?;
+                self.handle_query(nexus_stmt, idx + 1 < stmt_count).await
+            }
+            .await;
+            match res {
+                Ok(res) => responses.extend(res),
+                Err(err) if responses.is_empty() => return Err(err),
+                Err(err) => {
+                    responses.push(Response::Error(Box::new(err.into())));
+                    break;
+                }
+            }
+        }
+        Ok(responses)
     }
 }
 
-fn parameter_to_string(portal: &Portal<NexusParsedStatement>, idx: usize) -> PgWireResult<String> {
-    // the index is managed from portal's parameters count so it's safe to
-    // unwrap here.
-    let param_type = portal.statement.parameter_types.get(idx).unwrap();
-    match param_type {
-        &Type::VARCHAR | &Type::TEXT => Ok(format!(
-            "'{}'",
-            portal
-                .parameter::<String>(idx, param_type)?
-                .map(|s| s.replace('\'', "''"))
-                .as_deref()
-                .unwrap_or("")
-        )),
-        &Type::BOOL => Ok(portal
-            .parameter::<bool>(idx, param_type)?
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| "".to_owned())),
-        &Type::INT4 => Ok(portal
-            .parameter::<i32>(idx, param_type)?
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| "".to_owned())),
-        &Type::INT8 => Ok(portal
-            .parameter::<i64>(idx, param_type)?
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| "".to_owned())),
-        &Type::FLOAT4 => Ok(portal
-            .parameter::<f32>(idx, param_type)?
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| "".to_owned())),
-        &Type::FLOAT8 => Ok(portal
-            .parameter::<f64>(idx, param_type)?
-            .map(|v| v.to_string())
-            .unwrap_or_else(|| "".to_owned())),
-        _ => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
-            "ERROR".to_owned(),
-            "22023".to_owned(),
-            "unsupported_parameter_value".to_owned(),
-        )))),
+fn parameter_to_value(portal: &Portal<NexusParsedStatement>, idx: usize) -> PgWireResult<Value> {
+    // parameters sent as text are passed as they are, the peer converts them to the
+    // type it inferred.
+    if !portal.parameter_format.is_binary(idx) {
+        let value = portal.parameter::<String>(idx, &Type::UNKNOWN)?;
+        return Ok(value.map_or(Value::Null, Value::Text));
     }
+
+    // binary parameters are decoded with the type declared by the client, or the one
+    // the peer inferred when the statement was described.
+    let statement = &portal.statement;
+    let param_type = statement
+        .parameter_types
+        .get(idx)
+        .filter(|param_type| **param_type != Type::UNKNOWN)
+        .or_else(|| {
+            statement
+                .statement
+                .inferred_parameter_types
+                .get()
+                .and_then(|param_types| param_types.get(idx))
+        })
+        .unwrap_or(&Type::UNKNOWN);
+    let value = match param_type {
+        &Type::UNKNOWN => {
+            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "42P18".to_owned(),
+                format!(
+                    "could not determine data type of parameter ${}, binary parameters need a declared type",
+                    idx + 1
+                ),
+            ))));
+        }
+        &Type::BOOL => portal.parameter::<bool>(idx, param_type)?.map(Value::Bool),
+        &Type::INT2 => portal
+            .parameter::<i16>(idx, param_type)?
+            .map(Value::SmallInt),
+        &Type::INT4 => portal
+            .parameter::<i32>(idx, param_type)?
+            .map(Value::Integer),
+        &Type::INT8 => portal.parameter::<i64>(idx, param_type)?.map(Value::BigInt),
+        &Type::FLOAT4 => portal.parameter::<f32>(idx, param_type)?.map(Value::Float),
+        &Type::FLOAT8 => portal.parameter::<f64>(idx, param_type)?.map(Value::Double),
+        _ => portal
+            .parameter::<String>(idx, param_type)?
+            .map(Value::Text),
+    };
+    Ok(value.unwrap_or(Value::Null))
 }
 
 #[async_trait]
@@ -808,7 +1917,7 @@ impl ExtendedQueryHandler for NexusBackend {
 
     async fn do_query<'a, C>(
         &self,
-        _client: &mut C,
+        client: &mut C,
         portal: &Portal<Self::Statement>,
         _max_rows: usize,
     ) -> PgWireResult<Response<'a>>
@@ -819,17 +1928,15 @@ impl ExtendedQueryHandler for NexusBackend {
         PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
     {
         let stmt = &portal.statement.statement;
-        tracing::info!("[eqp] do_query: {}", stmt.query);
-
-        // manually replace variables in prepared statement
-        let mut sql = stmt.query.clone();
-        for i in 0..portal.parameter_len() {
-            sql = sql.replace(&format!("${}", i + 1), &parameter_to_string(portal, i)?);
-        }
-
-        let parsed = self.query_parser.parse_simple_sql(&sql).await?;
-        let nexus_stmt = parsed.statement;
-        let result = self.handle_query(nexus_stmt).await?;
+        tracing::info!("[eqp] do_query: {}", stmt.loggable_query());
+        self.check_manages_users(client, &stmt.statement)?;
+
+        let params = (0..portal.parameter_len())
+            .map(|idx| parameter_to_value(portal, idx))
+            .collect::<PgWireResult<Vec<_>>>()?;
+        let result = self
+            .handle_query_with_params(stmt.statement.clone(), &params)
+            .await?;
         if result.is_empty() {
             Ok(Response::EmptyQuery)
         } else {
@@ -862,11 +1969,20 @@ impl ExtendedQueryHandler for NexusBackend {
     where
         C: ClientInfo + Unpin + Send + Sync,
     {
+        let param_types = self
+            .do_describe_params(&target.statement, &target.parameter_types)
+            .await?;
+        // kept for the portals bound to the statement later on
+        target
+            .statement
+            .inferred_parameter_types
+            .set(param_types.clone())
+            .ok();
         Ok(
             if let Some(schema) = self.do_describe(&target.statement).await? {
-                DescribeStatementResponse::new(target.parameter_types.clone(), (*schema).clone())
+                DescribeStatementResponse::new(param_types, (*schema).clone())
             } else {
-                DescribeStatementResponse::no_data()
+                DescribeStatementResponse::new(param_types, vec![])
             },
         )
     }
@@ -909,6 +2025,11 @@ struct Args {
     #[clap(long, default_value = "postgres", env = "PEERDB_CATALOG_DATABASE")]
     catalog_database: String,
 
+    /// Maximum number of connections to the catalog shared by all sessions.
+    /// Defaults to `16`.
+    #[clap(long, default_value_t = 16, env = "PEERDB_CATALOG_POOL_SIZE")]
+    catalog_pool_size: usize,
+
     /// Path to the TLS certificate file.
     #[clap(long, requires = "tls_key", env = "PEERDB_TLS_CERT")]
     tls_cert: Option<String>,
@@ -917,6 +2038,21 @@ struct Args {
     #[clap(long, requires = "tls_cert", env = "PEERDB_TLS_KEY")]
     tls_key: Option<String>,
 
+    /// Path to a CA bundle, if set clients must present a certificate signed by it.
+    #[clap(long, requires = "tls_cert", env = "PEERDB_TLS_CLIENT_CA")]
+    tls_client_ca: Option<String>,
+
+    /// If set to true, a client certificate whose CN or SAN is the name of a nexus user
+    /// logs that user in without a password.
+    #[clap(
+        long,
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        requires = "tls_client_ca",
+        env = "PEERDB_TLS_CLIENT_CERT_AUTH"
+    )]
+    tls_client_cert_auth: bool,
+
     /// Path to the directory where peerdb logs will be written to.
     #[clap(short, long, env = "PEERDB_LOG_DIR")]
     log_dir: Option<String>,
@@ -927,6 +2063,16 @@ struct Args {
     #[clap(long, env = "PEERDB_PASSWORD", default_value = "peerdb")]
     peerdb_password: String,
 
+    /// If set to true, users without a catalog entry may log in with `PEERDB_PASSWORD`.
+    /// Set to false once every client has been given its own user.
+    #[clap(
+        long,
+        default_value = "true",
+        action = clap::ArgAction::Set,
+        env = "PEERDB_ALLOW_SHARED_PASSWORD"
+    )]
+    allow_shared_password: bool,
+
     /// Points to the URL for the Flow API server.
     ///
     /// This is an optional parameter. If not provided, the MIRROR commands will not be supported.
@@ -936,6 +2082,12 @@ struct Args {
     #[clap(long, default_value = "false", env = "PEERDB_FDW_MODE")]
     peerdb_fdw_mode: bool,
 
+    /// The nexus user allowed to create, alter and drop users.
+    ///
+    /// Defaults to `peerdb`.
+    #[clap(long, default_value = "peerdb", env = "PEERDB_ADMIN_USER")]
+    admin_user: String,
+
     /// If set to true, nexus will exit after running migrations
     #[clap(long, default_value = "false", env = "PEERDB_MIGRATIONS_ONLY")]
     migrations_only: bool,
@@ -944,6 +2096,15 @@ struct Args {
     #[clap(long, default_value = "false", env = "PEERDB_MIGRATIONS_DISABLED")]
     migrations_disabled: bool,
 
+    /// Seconds that active sessions are given to finish after SIGTERM or SIGINT,
+    /// sessions still open after that are terminated.
+    #[clap(long, default_value_t = 30, env = "PEERDB_SHUTDOWN_GRACE_PERIOD")]
+    shutdown_grace_period: u64,
+
+    /// Seconds after which a pooled peer connection that no session checked out is closed.
+    #[clap(long, default_value_t = 600, env = "PEERDB_PEER_EXECUTOR_IDLE_TIMEOUT")]
+    peer_executor_idle_timeout: u64,
+
     /// KMS Key ID for decrypting the catalog password
     #[clap(long, env = "PEERDB_KMS_KEY_ID")]
     kms_key_id: Option<Arc<String>>,
@@ -1022,8 +2183,8 @@ async fn run_migrations(
     // retry connecting to the catalog 3 times with 30 seconds delay
     // if it fails, return an error
     for _ in 0..3 {
-        match Catalog::new(config.to_postgres_config(), kms_key_id).await {
-            Ok(mut catalog) => {
+        match Catalog::new(config.to_postgres_config(), kms_key_id, 1).await {
+            Ok(catalog) => {
                 catalog.run_migrations().await?;
                 return Ok(());
             }
@@ -1050,8 +2211,22 @@ fn setup_tls(args: &Args) -> Result<Option<TlsAcceptor>, io::Error> {
             .collect::<Result<Vec<PrivateKeyDer>, io::Error>>()?
             .remove(0);
 
-        let mut config = ServerConfig::builder()
-            .with_no_client_auth()
+        let config = ServerConfig::builder();
+        let config = if let Some(tls_client_ca) = args.tls_client_ca.as_deref() {
+            let mut roots = RootCertStore::empty();
+            for ca_cert in certs(&mut io::BufReader::new(File::open(tls_client_ca)?)) {
+                roots
+                    .add(ca_cert?)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .build()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            config.with_client_cert_verifier(verifier)
+        } else {
+            config.with_no_client_auth()
+        };
+        let mut config = config
             .with_single_cert(cert, key)
             .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
 
@@ -1064,11 +2239,10 @@ fn setup_tls(args: &Args) -> Result<Option<TlsAcceptor>, io::Error> {
 }
 
 pub struct Handlers {
-    authenticator: (
-        Arc<FixedPasswordAuthSource>,
-        Arc<NexusServerParameterProvider>,
-    ),
+    authenticator: (Arc<CatalogAuthSource>, Arc<NexusServerParameterProvider>),
+    client_cert_auth: ClientCertAuth,
     nexus: Arc<NexusBackend>,
+    cancel_key: (i32, i32),
 }
 
 impl PgWireServerHandlers for Handlers {
@@ -1081,10 +2255,19 @@ impl PgWireServerHandlers for Handlers {
     }
 
     fn startup_handler(&self) -> Arc<impl StartupHandler> {
-        Arc::new(SASLScramAuthStartupHandler::new(
-            self.authenticator.0.clone(),
-            self.authenticator.1.clone(),
-        ))
+        Arc::new(CancelKeyStartupHandler {
+            inner: ClientCertStartupHandler {
+                inner: SASLScramAuthStartupHandler::new(
+                    self.authenticator.0.clone(),
+                    self.authenticator.1.clone(),
+                ),
+                auth_source: self.authenticator.0.clone(),
+                parameter_provider: self.authenticator.1.clone(),
+                mode: self.client_cert_auth,
+            },
+            pid: self.cancel_key.0,
+            secret_key: self.cancel_key.1,
+        })
     }
 }
 
@@ -1109,18 +2292,44 @@ pub async fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
+    // user lookups during startup and the sessions share one pool of catalog connections
+    let catalog = Arc::new(
+        Catalog::new(
+            catalog_config.to_postgres_config(),
+            &args.kms_key_id,
+            args.catalog_pool_size,
+        )
+        .await?,
+    );
+    tokio::spawn(catalog.clone().listen_for_peer_changes());
     let authenticator = (
-        Arc::new(FixedPasswordAuthSource::new(args.peerdb_password.clone())),
+        Arc::new(CatalogAuthSource::new(
+            catalog.clone(),
+            args.peerdb_password.clone(),
+            args.allow_shared_password,
+        )),
         Arc::new(NexusServerParameterProvider),
     );
 
     let tls_acceptor = setup_tls(&args)?;
+    let client_cert_auth = if args.tls_client_cert_auth {
+        ClientCertAuth::MapToUser
+    } else if args.tls_client_ca.is_some() {
+        ClientCertAuth::Required
+    } else {
+        ClientCertAuth::Disabled
+    };
 
     let peer_conns = {
         let conn_str = catalog_config.to_pg_connection_string();
         let pconns = PeerConnections::new(&conn_str)?;
         Arc::new(pconns)
     };
+    let peer_executors = Arc::new(ExecutorRegistry::new(
+        peer_conns.clone(),
+        Duration::from_secs(args.peer_executor_idle_timeout),
+    ));
+    tokio::spawn(peer_executors.clone().evict_idle());
 
     let server_addr = format!("{}:{}", args.host, args.port);
     let listener = TcpListener::bind(&server_addr).await.unwrap();
@@ -1135,59 +2344,78 @@ pub async fn main() -> anyhow::Result<()> {
         None
     };
 
+    let cancel_keys = Arc::new(CancelKeys::default());
+
     let mut sigintstream = signal(SignalKind::interrupt()).expect("Failed to setup signal handler");
+    let mut sigtermstream =
+        signal(SignalKind::terminate()).expect("Failed to setup signal handler");
+    let (terminate_tx, terminate_rx) = watch::channel(false);
+    let mut sessions = JoinSet::new();
     loop {
         let (mut socket, _) = tokio::select! {
-            _ = sigintstream.recv() => return Ok(()),
+            _ = sigintstream.recv() => break,
+            _ = sigtermstream.recv() => break,
+            Some(_) = sessions.join_next(), if !sessions.is_empty() => continue,
             v = listener.accept() => v,
         }?;
         let conn_flow_handler = flow_handler.clone();
-        let conn_peer_conns = peer_conns.clone();
+        let peer_executors = peer_executors.clone();
         let authenticator = authenticator.clone();
-        let pg_config = catalog_config.to_postgres_config();
-        let kms_key_id = args.kms_key_id.clone();
+        let catalog = catalog.clone();
         let tls_acceptor = tls_acceptor.clone();
+        let cancel_keys = cancel_keys.clone();
+        let terminate_rx = terminate_rx.clone();
+        let admin_user = args.admin_user.clone();
 
-        tokio::task::spawn(async move {
-            match Catalog::new(pg_config, &kms_key_id).await {
-                Ok(catalog) => {
-                    let conn_uuid = uuid::Uuid::new_v4();
-                    let tracker = PeerConnectionTracker::new(conn_uuid, conn_peer_conns);
-
-                    let nexus = Arc::new(NexusBackend::new(
-                        Arc::new(catalog),
-                        tracker,
-                        conn_flow_handler,
-                        args.peerdb_fdw_mode,
-                    ));
-                    process_socket(
-                        socket,
-                        tls_acceptor,
-                        Arc::new(Handlers {
-                            nexus,
-                            authenticator,
-                        }),
-                    )
-                    .await
-                }
-                Err(e) => {
-                    tracing::error!("Failed to connect to catalog: {}", e);
-
-                    let mut buf = BytesMut::with_capacity(1024);
-                    buf.put_u8(b'E');
-                    buf.put_i32(0);
-                    buf.put(&b"FATAL"[..]);
-                    buf.put_u8(0);
-                    write!(buf, "Failed to connect to catalog: {e}").ok();
-                    buf.put_u8(0);
-                    buf.put_u8(b'\0');
-
-                    socket.write_all(&buf).await?;
-                    socket.shutdown().await?;
-
-                    Ok(())
-                }
+        sessions.spawn(async move {
+            if let Some((pid, secret_key)) = cancel::read_cancel_request(&mut socket).await? {
+                cancel_keys.cancel(pid, secret_key).await;
+                return Ok(());
             }
+            let nexus = Arc::new(NexusBackend::new(
+                catalog,
+                peer_executors,
+                conn_flow_handler,
+                args.peerdb_fdw_mode,
+                admin_user,
+            ));
+            let cancel_key = cancel_keys.register(&nexus);
+
+            let handlers = Arc::new(Handlers {
+                nexus: nexus.clone(),
+                authenticator,
+                client_cert_auth,
+                cancel_key,
+            });
+            let res =
+                session::process_socket(socket, tls_acceptor, handlers, &nexus, terminate_rx).await;
+            cancel_keys.unregister(cancel_key.0);
+            nexus.release_executors().await;
+            res
         });
     }
+
+    // stop accepting connections and give the active sessions the grace period
+    // to finish, a second signal terminates them right away.
+    drop(listener);
+    tracing::info!(
+        "shutting down, waiting up to {} seconds for {} sessions",
+        args.shutdown_grace_period,
+        sessions.len()
+    );
+    tokio::select! {
+        _ = async { while sessions.join_next().await.is_some() {} } => return Ok(()),
+        _ = tokio::time::sleep(Duration::from_secs(args.shutdown_grace_period)) => {}
+        _ = sigintstream.recv() => {}
+        _ = sigtermstream.recv() => {}
+    }
+
+    tracing::info!("terminating {} remaining sessions", sessions.len());
+    terminate_tx.send(true).ok();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while sessions.join_next().await.is_some() {}
+    })
+    .await
+    .ok();
+    Ok(())
 }
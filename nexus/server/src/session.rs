@@ -0,0 +1,441 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Sink, SinkExt, StreamExt};
+use pgwire::{
+    api::{
+        ClientInfo, ClientPortalStore, DefaultClient, PgWireConnectionState, PgWireServerHandlers,
+        auth::StartupHandler,
+        query::{ExtendedQueryHandler, SimpleQueryHandler},
+        store::MemPortalStore,
+    },
+    error::{ErrorInfo, PgWireError, PgWireResult},
+    messages::{
+        DecodeContext, PgWireBackendMessage, PgWireFrontendMessage, ProtocolVersion,
+        response::{ReadyForQuery, SslResponse, TransactionStatus},
+        startup::{SecretKey, SslRequest},
+    },
+};
+use rustls_pki_types::CertificateDer;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    sync::watch,
+};
+use tokio_rustls::TlsAcceptor;
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+use crate::NexusBackend;
+
+const POSTGRESQL_ALPN_NAME: &[u8] = b"postgresql";
+const TLS_HANDSHAKE: u8 = 0x16;
+
+/// Codec of the messages of a session, the state of the client is kept next to it as the
+/// frontend messages are decoded differently until the startup is done.
+struct SessionCodec<ST> {
+    client: DefaultClient<ST>,
+}
+
+impl<ST> SessionCodec<ST> {
+    fn new(socket_addr: SocketAddr, is_secure: bool) -> Self {
+        Self {
+            client: DefaultClient::new(socket_addr, is_secure),
+        }
+    }
+}
+
+impl<ST> Decoder for SessionCodec<ST> {
+    type Item = PgWireFrontendMessage;
+    type Error = PgWireError;
+
+    fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let mut decode_context = DecodeContext::new(self.client.protocol_version());
+        match self.client.state() {
+            PgWireConnectionState::AwaitingSslRequest => {}
+            PgWireConnectionState::AwaitingStartup => {
+                decode_context.awaiting_ssl = false;
+            }
+            _ => {
+                decode_context.awaiting_ssl = false;
+                decode_context.awaiting_startup = false;
+            }
+        }
+        PgWireFrontendMessage::decode(src, &decode_context)
+    }
+}
+
+impl<ST> Encoder<PgWireBackendMessage> for SessionCodec<ST> {
+    type Error = io::Error;
+
+    fn encode(
+        &mut self,
+        item: PgWireBackendMessage,
+        dst: &mut bytes::BytesMut,
+    ) -> Result<(), Self::Error> {
+        item.encode(dst).map_err(Into::into)
+    }
+}
+
+/// The connection of a client, plaintext or TLS, as seen by the handlers.
+struct Session<S, ST> {
+    framed: Framed<S, SessionCodec<ST>>,
+    // the certificates presented by the client during the TLS handshake
+    certificates: Option<Vec<CertificateDer<'static>>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin, ST> Sink<PgWireBackendMessage> for Session<S, ST> {
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.framed).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: PgWireBackendMessage) -> io::Result<()> {
+        Pin::new(&mut self.framed).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.framed).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.framed).poll_close(cx)
+    }
+}
+
+impl<S, ST> ClientInfo for Session<S, ST> {
+    fn socket_addr(&self) -> SocketAddr {
+        self.framed.codec().client.socket_addr()
+    }
+
+    fn is_secure(&self) -> bool {
+        self.framed.codec().client.is_secure()
+    }
+
+    fn protocol_version(&self) -> ProtocolVersion {
+        self.framed.codec().client.protocol_version()
+    }
+
+    fn set_protocol_version(&mut self, version: ProtocolVersion) {
+        self.framed.codec_mut().client.set_protocol_version(version);
+    }
+
+    fn pid_and_secret_key(&self) -> (i32, SecretKey) {
+        self.framed.codec().client.pid_and_secret_key()
+    }
+
+    fn set_pid_and_secret_key(&mut self, pid: i32, secret_key: SecretKey) {
+        self.framed
+            .codec_mut()
+            .client
+            .set_pid_and_secret_key(pid, secret_key);
+    }
+
+    fn state(&self) -> PgWireConnectionState {
+        self.framed.codec().client.state()
+    }
+
+    fn set_state(&mut self, new_state: PgWireConnectionState) {
+        self.framed.codec_mut().client.set_state(new_state);
+    }
+
+    fn transaction_status(&self) -> TransactionStatus {
+        self.framed.codec().client.transaction_status()
+    }
+
+    fn set_transaction_status(&mut self, new_status: TransactionStatus) {
+        self.framed
+            .codec_mut()
+            .client
+            .set_transaction_status(new_status);
+    }
+
+    fn metadata(&self) -> &HashMap<String, String> {
+        self.framed.codec().client.metadata()
+    }
+
+    fn metadata_mut(&mut self) -> &mut HashMap<String, String> {
+        self.framed.codec_mut().client.metadata_mut()
+    }
+
+    fn client_certificates<'a>(&self) -> Option<&[CertificateDer<'a>]> {
+        self.certificates.as_deref()
+    }
+}
+
+impl<S, ST> ClientPortalStore for Session<S, ST> {
+    type PortalStore = MemPortalStore<ST>;
+
+    fn portal_store(&self) -> &Self::PortalStore {
+        self.framed.codec().client.portal_store()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum SslNegotiation {
+    Postgres,
+    Direct,
+    None,
+}
+
+// answers the SslRequest the client may open the connection with
+async fn negotiate_ssl<ST>(
+    framed: &mut Framed<TcpStream, SessionCodec<ST>>,
+    ssl_supported: bool,
+) -> io::Result<SslNegotiation> {
+    let mut buf = [0u8; 8];
+    let n = framed.get_ref().peek(&mut buf).await?;
+    if ssl_supported && n > 0 && buf[0] == TLS_HANDSHAKE {
+        return Ok(SslNegotiation::Direct);
+    }
+    if n < buf.len() || !SslRequest::is_ssl_request_packet(&buf) {
+        return Ok(SslNegotiation::None);
+    }
+
+    // consume the SslRequest, the startup message follows
+    framed.next().await;
+    framed
+        .codec_mut()
+        .client
+        .set_state(PgWireConnectionState::AwaitingStartup);
+    if ssl_supported {
+        framed
+            .send(PgWireBackendMessage::SslResponse(SslResponse::Accept))
+            .await?;
+        Ok(SslNegotiation::Postgres)
+    } else {
+        framed
+            .send(PgWireBackendMessage::SslResponse(SslResponse::Refuse))
+            .await?;
+        Ok(SslNegotiation::None)
+    }
+}
+
+/// Serve a client connection until the client leaves, like pgwire's `process_socket`.
+/// Once `terminate` is set by the shutdown the query running for the client is
+/// cancelled, and the client is sent a FATAL error as soon as the message being
+/// processed is answered.
+pub async fn process_socket<H: PgWireServerHandlers>(
+    socket: TcpStream,
+    tls_acceptor: Option<TlsAcceptor>,
+    handlers: H,
+    nexus: &NexusBackend,
+    mut terminate: watch::Receiver<bool>,
+) -> io::Result<()> {
+    let addr = socket.peer_addr()?;
+    socket.set_nodelay(true)?;
+    let startup_handler = handlers.startup_handler();
+    let simple_query_handler = handlers.simple_query_handler();
+    let extended_query_handler = handlers.extended_query_handler();
+
+    // the client is disconnected without a word until it has started its session
+    let mut framed = Framed::new(socket, SessionCodec::new(addr, false));
+    let ssl = tokio::select! {
+        ssl = negotiate_ssl(&mut framed, tls_acceptor.is_some()) => ssl?,
+        _ = terminate.wait_for(|terminate| *terminate) => return Ok(()),
+    };
+    match (ssl, tls_acceptor) {
+        (SslNegotiation::Postgres | SslNegotiation::Direct, Some(tls_acceptor)) => {
+            let tls_socket = tokio::select! {
+                tls_socket = tls_acceptor.accept(framed.into_inner()) => tls_socket?,
+                _ = terminate.wait_for(|terminate| *terminate) => return Ok(()),
+            };
+            let (_, connection) = tls_socket.get_ref();
+            if ssl == SslNegotiation::Direct
+                && connection.alpn_protocol() != Some(POSTGRESQL_ALPN_NAME)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "received direct SSL connection request without ALPN protocol negotiation extension",
+                ));
+            }
+            let certificates = connection
+                .peer_certificates()
+                .map(|certs| certs.iter().map(|cert| cert.clone().into_owned()).collect());
+            let session = Session {
+                framed: Framed::new(tls_socket, SessionCodec::new(addr, true)),
+                certificates,
+            };
+            session
+                .serve(
+                    &*startup_handler,
+                    &*simple_query_handler,
+                    &*extended_query_handler,
+                    nexus,
+                    terminate,
+                )
+                .await
+        }
+        _ => {
+            let session = Session {
+                framed,
+                certificates: None,
+            };
+            session
+                .serve(
+                    &*startup_handler,
+                    &*simple_query_handler,
+                    &*extended_query_handler,
+                    nexus,
+                    terminate,
+                )
+                .await
+        }
+    }
+}
+
+impl<S, ST> Session<S, ST>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+    ST: Clone + Send + Sync,
+{
+    async fn serve<A, Q, EQ>(
+        mut self,
+        startup_handler: &A,
+        simple_query_handler: &Q,
+        extended_query_handler: &EQ,
+        nexus: &NexusBackend,
+        mut terminate: watch::Receiver<bool>,
+    ) -> io::Result<()>
+    where
+        A: StartupHandler,
+        Q: SimpleQueryHandler,
+        EQ: ExtendedQueryHandler<Statement = ST>,
+    {
+        self.set_state(PgWireConnectionState::AwaitingStartup);
+        loop {
+            // a shutdown which came while the previous message was processed goes first
+            let message = tokio::select! {
+                biased;
+                _ = terminate.wait_for(|terminate| *terminate) => break,
+                message = self.framed.next() => message,
+            };
+            let Some(Ok(message)) = message else {
+                return Ok(());
+            };
+
+            let is_extended_query = message.is_extended_query();
+            let res = {
+                let processing = self.process_message(
+                    message,
+                    startup_handler,
+                    simple_query_handler,
+                    extended_query_handler,
+                );
+                tokio::pin!(processing);
+                tokio::select! {
+                    res = &mut processing => res,
+                    _ = terminate.wait_for(|terminate| *terminate) => {
+                        // the handler is left to answer the client once its query is cancelled
+                        nexus.cancel().await;
+                        processing.await
+                    }
+                }
+            };
+            if let Err(err) = res {
+                let error_info = ErrorInfo::from(err);
+                let is_fatal = error_info.is_fatal();
+                self.process_error(error_info, is_extended_query).await?;
+                if is_fatal {
+                    return self.close().await;
+                }
+            }
+        }
+
+        let error_info = ErrorInfo::new(
+            "FATAL".to_owned(),
+            "57P01".to_owned(),
+            "terminating connection due to administrator command".to_owned(),
+        );
+        self.send(PgWireBackendMessage::ErrorResponse(error_info.into()))
+            .await?;
+        self.close().await
+    }
+
+    async fn process_message<A, Q, EQ>(
+        &mut self,
+        message: PgWireFrontendMessage,
+        startup_handler: &A,
+        simple_query_handler: &Q,
+        extended_query_handler: &EQ,
+    ) -> PgWireResult<()>
+    where
+        A: StartupHandler,
+        Q: SimpleQueryHandler,
+        EQ: ExtendedQueryHandler<Statement = ST>,
+    {
+        // cancel requests are answered before the session starts, the connection of one
+        // that made it here is closed
+        if let PgWireFrontendMessage::CancelRequest(_) = message {
+            return Ok(self.close().await?);
+        }
+
+        match self.state() {
+            PgWireConnectionState::AwaitingStartup
+            | PgWireConnectionState::AuthenticationInProgress => {
+                startup_handler.on_startup(self, message).await?;
+            }
+            // after an error in the extended protocol the messages are discarded until Sync
+            PgWireConnectionState::AwaitingSync => {
+                if let PgWireFrontendMessage::Sync(sync) = message {
+                    extended_query_handler.on_sync(self, sync).await?;
+                    self.set_state(PgWireConnectionState::ReadyForQuery);
+                }
+            }
+            _ => match message {
+                PgWireFrontendMessage::Query(query) => {
+                    simple_query_handler.on_query(self, query).await?;
+                }
+                PgWireFrontendMessage::Parse(parse) => {
+                    extended_query_handler.on_parse(self, parse).await?;
+                }
+                PgWireFrontendMessage::Bind(bind) => {
+                    extended_query_handler.on_bind(self, bind).await?;
+                }
+                PgWireFrontendMessage::Execute(execute) => {
+                    extended_query_handler.on_execute(self, execute).await?;
+                }
+                PgWireFrontendMessage::Describe(describe) => {
+                    extended_query_handler.on_describe(self, describe).await?;
+                }
+                PgWireFrontendMessage::Flush(flush) => {
+                    extended_query_handler.on_flush(self, flush).await?;
+                }
+                PgWireFrontendMessage::Sync(sync) => {
+                    extended_query_handler.on_sync(self, sync).await?;
+                }
+                PgWireFrontendMessage::Close(close) => {
+                    extended_query_handler.on_close(self, close).await?;
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    async fn process_error(
+        &mut self,
+        error_info: ErrorInfo,
+        wait_for_sync: bool,
+    ) -> io::Result<()> {
+        self.feed(PgWireBackendMessage::ErrorResponse(error_info.into()))
+            .await?;
+        let transaction_status = self.transaction_status().to_error_state();
+        self.set_transaction_status(transaction_status);
+        if wait_for_sync {
+            self.set_state(PgWireConnectionState::AwaitingSync);
+        } else {
+            self.set_state(PgWireConnectionState::ReadyForQuery);
+            self.feed(PgWireBackendMessage::ReadyForQuery(ReadyForQuery::new(
+                transaction_status,
+            )))
+            .await?;
+        }
+        self.flush().await
+    }
+}
@@ -0,0 +1,814 @@
+use std::{collections::HashMap, sync::Arc};
+
+use analyzer::{PeerDDL, PeerDDLAnalyzer, StatementAnalyzer};
+use flow_rs::grpc;
+use peer_cursor::{Record, Records, Schema};
+use pgwire::api::{
+    Type,
+    results::{FieldFormat, FieldInfo},
+};
+use pt::{
+    peerdb_flow::{DynconfApplyMode, FlowStatus, QRepWriteType, TypeSystem},
+    peerdb_peers::{
+        AwsAuthenticationConfig, DbType, Peer, S3Config, SshConfig,
+        aws_authentication_config::AuthConfig, peer::Config,
+    },
+    peerdb_route::{
+        AlertConfig, CdcBatch, CloneTableSummary, DynamicSetting, ListMirrorsItem,
+        MaintenancePhase, MaintenanceStatusResponse, MirrorLog, MirrorStatusResponse, PeerListItem,
+        Script, mirror_status_response,
+    },
+};
+use serde_json::{Value as JsonValue, json};
+use sqlparser::ast::Statement;
+use value::Value;
+
+/// Number of batches listed by `DESCRIBE MIRROR`.
+pub const RECENT_BATCHES: u32 = 10;
+
+const REDACTED: &str = "********";
+// options of the alert service configs which hold credentials, by service type
+const ALERT_SECRET_OPTIONS: &[(&str, &[&str])] = &[("slack", &["auth_token"])];
+
+fn text_schema(columns: &[&str]) -> Schema {
+    Arc::new(
+        columns
+            .iter()
+            .map(|name| FieldInfo::new(name.to_string(), None, None, Type::TEXT, FieldFormat::Text))
+            .collect(),
+    )
+}
+
+pub fn peers_schema() -> Schema {
+    text_schema(&["name", "type", "options"])
+}
+
+pub fn mirrors_schema() -> Schema {
+    text_schema(&["name", "source", "target", "kind", "status", "tags"])
+}
+
+pub fn problems_schema() -> Schema {
+    text_schema(&["name", "problem"])
+}
+
+pub fn config_schema() -> Schema {
+    text_schema(&["name", "value"])
+}
+
+pub fn alerts_schema() -> Schema {
+    let column =
+        |name: &str, r#type| FieldInfo::new(name.to_owned(), None, None, r#type, FieldFormat::Text);
+    Arc::new(vec![
+        column("id", Type::INT4),
+        column("service", Type::TEXT),
+        column("config", Type::TEXT),
+        column("mirrors", Type::TEXT),
+    ])
+}
+
+pub fn settings_schema() -> Schema {
+    text_schema(&["name", "value", "default", "description", "apply_mode"])
+}
+
+pub fn scripts_schema() -> Schema {
+    text_schema(&["name", "language", "source"])
+}
+
+pub fn maintenance_schema() -> Schema {
+    let column =
+        |name: &str, r#type| FieldInfo::new(name.to_owned(), None, None, r#type, FieldFormat::Text);
+    Arc::new(vec![
+        column("name", Type::TEXT),
+        column("value", Type::TEXT),
+        column("last_heartbeat", Type::TIMESTAMPTZ),
+    ])
+}
+
+pub fn mirror_status_schema() -> Schema {
+    let column =
+        |name: &str, r#type| FieldInfo::new(name.to_owned(), None, None, r#type, FieldFormat::Text);
+    Arc::new(vec![
+        column("section", Type::TEXT),
+        column("name", Type::TEXT),
+        column("value", Type::TEXT),
+        column("time", Type::TIMESTAMPTZ),
+    ])
+}
+
+/// Rows of `SHOW PEERS`, the options come from the catalog with their credentials redacted.
+pub fn peers_records(
+    items: Vec<PeerListItem>,
+    peers: &HashMap<String, Peer>,
+) -> anyhow::Result<Records> {
+    let schema = peers_schema();
+    let records = items
+        .into_iter()
+        .map(|item| {
+            let peer_type = DbType::try_from(item.r#type)
+                .map(|db_type| db_type.as_str_name())
+                .unwrap_or("UNKNOWN");
+            let options = match peers.get(&item.name) {
+                Some(peer) => Value::Text(redacted_options(peer)?.to_string()),
+                None => Value::Null,
+            };
+            Ok(Record {
+                values: vec![
+                    Value::Text(item.name),
+                    Value::Text(peer_type.to_owned()),
+                    options,
+                ],
+                schema: schema.clone(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Records { records, schema })
+}
+
+/// Rows of `SHOW MIRRORS`, mirrors whose status could not be fetched have a null status.
+/// Tags are listed as `key=value` pairs sorted by key.
+pub fn mirrors_records(
+    mirrors: Vec<(ListMirrorsItem, HashMap<String, String>, Option<FlowStatus>)>,
+) -> Records {
+    let schema = mirrors_schema();
+    let records = mirrors
+        .into_iter()
+        .map(|(mirror, tags, status)| {
+            let kind = if mirror.is_cdc { "CDC" } else { "QRep" };
+            let mut tags = tags
+                .into_iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>();
+            tags.sort();
+            Record {
+                values: vec![
+                    Value::Text(mirror.name),
+                    Value::Text(mirror.source_name),
+                    Value::Text(mirror.destination_name),
+                    Value::Text(kind.to_owned()),
+                    status.map(status_name).map_or(Value::Null, Value::Text),
+                    Value::Text(tags.join(",")),
+                ],
+                schema: schema.clone(),
+            }
+        })
+        .collect();
+    Records { records, schema }
+}
+
+/// Rows of `DESCRIBE MIRROR`: the state of the mirror, the snapshot progress of each
+/// table, the recent batches and the last error, each row tagged with its section.
+pub fn mirror_status_records(
+    status: MirrorStatusResponse,
+    summaries: Vec<CloneTableSummary>,
+    batches: Vec<CdcBatch>,
+    last_error: Option<MirrorLog>,
+) -> Records {
+    let schema = mirror_status_schema();
+    let mut rows = Vec::new();
+    let flow_status = FlowStatus::try_from(status.current_flow_state)
+        .map(status_name)
+        .unwrap_or_else(|_| "unknown".to_owned());
+    let created_at = status
+        .created_at
+        .map_or(Value::Null, |ts| timestamp(ts.seconds, ts.nanos));
+    rows.push(("state", "status".to_owned(), flow_status, created_at));
+    match status.status {
+        Some(mirror_status_response::Status::CdcStatus(cdc)) => {
+            if let Some(config) = cdc.config {
+                let source = config.source_name;
+                rows.push(("state", "source".to_owned(), source, Value::Null));
+                let destination = config.destination_name;
+                rows.push(("state", "destination".to_owned(), destination, Value::Null));
+            }
+            let rows_synced = cdc.rows_synced.to_string();
+            rows.push(("state", "rows_synced".to_owned(), rows_synced, Value::Null));
+        }
+        Some(mirror_status_response::Status::QrepStatus(qrep)) => {
+            let partitions = qrep.partitions.len().to_string();
+            rows.push(("state", "partitions".to_owned(), partitions, Value::Null));
+        }
+        None => {}
+    }
+
+    for summary in summaries {
+        let progress = format!(
+            "{}/{} partitions, {} rows",
+            summary.num_partitions_completed, summary.num_partitions_total, summary.num_rows_synced
+        );
+        let start_time = summary
+            .start_time
+            .map_or(Value::Null, |ts| timestamp(ts.seconds, ts.nanos));
+        rows.push(("snapshot", summary.table_name, progress, start_time));
+    }
+
+    for batch in batches {
+        let end_time = batch
+            .end_time
+            .map_or(Value::Null, |ts| timestamp(ts.seconds, ts.nanos));
+        let num_rows = format!("{} rows", batch.num_rows);
+        rows.push(("batch", batch.batch_id.to_string(), num_rows, end_time));
+    }
+
+    if let Some(error) = last_error {
+        // error timestamps are sent as milliseconds since the epoch
+        let error_time = chrono::DateTime::from_timestamp_millis(error.error_timestamp as i64)
+            .map_or(Value::Null, Value::TimestampWithTimeZone);
+        rows.push(("error", error.error_type, error.error_message, error_time));
+    }
+
+    let records = rows
+        .into_iter()
+        .map(|(section, name, value, time)| Record {
+            values: vec![
+                Value::Text(section.to_owned()),
+                Value::Text(name),
+                Value::Text(value),
+                time,
+            ],
+            schema: schema.clone(),
+        })
+        .collect();
+    Records { records, schema }
+}
+
+fn timestamp(seconds: i64, nanos: i32) -> Value {
+    chrono::DateTime::from_timestamp(seconds, nanos as u32)
+        .map_or(Value::Null, Value::TimestampWithTimeZone)
+}
+
+/// Rows of `SHOW ALERTS`, with the credentials of each config redacted. Alerts which are
+/// not limited to some mirrors have null mirrors.
+pub fn alerts_records(configs: Vec<AlertConfig>) -> anyhow::Result<Records> {
+    let schema = alerts_schema();
+    let records = configs
+        .into_iter()
+        .map(|config| {
+            let mut service_config: JsonValue = serde_json::from_str(&config.service_config)?;
+            redact_alert_config(&config.service_type, &mut service_config);
+            let mirrors = if config.alert_for_mirrors.is_empty() {
+                Value::Null
+            } else {
+                Value::Text(config.alert_for_mirrors.join(","))
+            };
+            Ok(Record {
+                values: vec![
+                    Value::Integer(config.id),
+                    Value::Text(config.service_type),
+                    Value::Text(service_config.to_string()),
+                    mirrors,
+                ],
+                schema: schema.clone(),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Records { records, schema })
+}
+
+/// Rows of `SHOW PEERDB SETTINGS`, settings which were never set have a null value.
+pub fn settings_records(settings: Vec<DynamicSetting>) -> Records {
+    let schema = settings_schema();
+    let records = settings
+        .into_iter()
+        .map(|setting| {
+            // APPLY_MODE_AFTER_RESUME is shown as after_resume
+            let apply_mode = DynconfApplyMode::try_from(setting.apply_mode)
+                .map(|mode| {
+                    mode.as_str_name()
+                        .trim_start_matches("APPLY_MODE_")
+                        .to_lowercase()
+                })
+                .unwrap_or_else(|_| "unknown".to_owned());
+            Record {
+                values: vec![
+                    Value::Text(setting.name),
+                    setting.value.map_or(Value::Null, Value::Text),
+                    Value::Text(setting.default_value),
+                    Value::Text(setting.description),
+                    Value::Text(apply_mode),
+                ],
+                schema: schema.clone(),
+            }
+        })
+        .collect();
+    Records { records, schema }
+}
+
+/// Rows of `SHOW SCRIPTS`, sorted by name.
+pub fn scripts_records(mut scripts: Vec<Script>) -> Records {
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    let schema = scripts_schema();
+    let records = scripts
+        .into_iter()
+        .map(|script| Record {
+            values: vec![
+                Value::Text(script.name),
+                Value::Text(script.lang),
+                Value::Text(script.source),
+            ],
+            schema: schema.clone(),
+        })
+        .collect();
+    Records { records, schema }
+}
+
+/// Rows of `SHOW MAINTENANCE STATUS`: whether maintenance is running and its phase,
+/// then the activities the maintenance workflows are still running.
+pub fn maintenance_records(status: MaintenanceStatusResponse) -> Records {
+    let schema = maintenance_schema();
+    // MAINTENANCE_PHASE_START_MAINTENANCE is shown as start_maintenance
+    let phase = MaintenancePhase::try_from(status.phase)
+        .map(|phase| {
+            phase
+                .as_str_name()
+                .trim_start_matches("MAINTENANCE_PHASE_")
+                .to_lowercase()
+        })
+        .unwrap_or_else(|_| "unknown".to_owned());
+    let mut rows = vec![
+        (
+            "running".to_owned(),
+            status.maintenance_running.to_string(),
+            Value::Null,
+        ),
+        ("phase".to_owned(), phase, Value::Null),
+    ];
+    for activity in status.pending_activities {
+        let last_heartbeat = activity
+            .last_heartbeat
+            .map_or(Value::Null, |ts| timestamp(ts.seconds, ts.nanos));
+        rows.push((
+            "activity".to_owned(),
+            activity.activity_name,
+            last_heartbeat,
+        ));
+    }
+
+    let records = rows
+        .into_iter()
+        .map(|(name, value, last_heartbeat)| Record {
+            values: vec![Value::Text(name), Value::Text(value), last_heartbeat],
+            schema: schema.clone(),
+        })
+        .collect();
+    Records { records, schema }
+}
+
+/// Rows of `VALIDATE PEER` and `VALIDATE MIRROR`, a valid definition has none.
+pub fn problems_records(problems: Vec<(String, String)>) -> Records {
+    let schema = problems_schema();
+    let records = problems
+        .into_iter()
+        .map(|(name, problem)| Record {
+            values: vec![Value::Text(name), Value::Text(problem)],
+            schema: schema.clone(),
+        })
+        .collect();
+    Records { records, schema }
+}
+
+/// Rows of `EXPLAIN CREATE MIRROR`: the config CREATE MIRROR would submit, one row per
+/// field with its value as json, in the order of flow.proto. Fields left at their default
+/// are shown with the default value.
+pub fn mirror_config_records(create_mirror: &Statement) -> anyhow::Result<Records> {
+    let (config, defaults) = match PeerDDLAnalyzer.analyze(create_mirror)? {
+        Some(PeerDDL::CreateMirrorForCDC { flow_job, .. }) => {
+            let source = flow_job.source_peer.clone();
+            let target = flow_job.target_peer.clone();
+            let config = grpc::cdc_flow_config(&flow_job, source, target)?;
+            (serde_json::to_value(config)?, cdc_config_defaults())
+        }
+        Some(PeerDDL::CreateMirrorForSelect { qrep_flow_job, .. }) => {
+            let source = qrep_flow_job.source_peer.clone();
+            let target = qrep_flow_job.target_peer.clone();
+            let config = grpc::qrep_config(&qrep_flow_job, source, target)?;
+            (serde_json::to_value(config)?, qrep_config_defaults())
+        }
+        _ => anyhow::bail!("expected a CREATE MIRROR statement"),
+    };
+
+    let schema = config_schema();
+    // fields holding their default value are left out when serializing
+    let JsonValue::Object(mut fields) = config else {
+        anyhow::bail!("mirror config is not a json object");
+    };
+    let mut rows: Vec<(String, JsonValue)> = defaults
+        .into_iter()
+        .map(|(name, default)| (name.to_owned(), fields.remove(name).unwrap_or(default)))
+        .collect();
+    // fields flow.proto gained since the lists below were written
+    rows.extend(fields);
+    let records = rows
+        .into_iter()
+        .map(|(name, value)| Record {
+            values: vec![Value::Text(name), Value::Text(value.to_string())],
+            schema: schema.clone(),
+        })
+        .collect();
+    Ok(Records { records, schema })
+}
+
+// the fields of FlowConnectionConfigs as serialized, with their default values
+fn cdc_config_defaults() -> Vec<(&'static str, JsonValue)> {
+    vec![
+        ("flowJobName", json!("")),
+        ("tableMappings", json!([])),
+        ("maxBatchSize", json!(0)),
+        // 64 bit integers are serialized as strings
+        ("idleTimeoutSeconds", json!("0")),
+        ("cdcStagingPath", json!("")),
+        ("publicationName", json!("")),
+        ("replicationSlotName", json!("")),
+        ("doInitialSnapshot", json!(false)),
+        ("snapshotNumRowsPerPartition", json!(0)),
+        ("snapshotStagingPath", json!("")),
+        ("snapshotMaxParallelWorkers", json!(0)),
+        ("snapshotNumTablesInParallel", json!(0)),
+        ("resync", json!(false)),
+        ("initialSnapshotOnly", json!(false)),
+        ("softDeleteColName", json!("")),
+        ("syncedAtColName", json!("")),
+        ("script", json!("")),
+        ("system", json!(TypeSystem::default().as_str_name())),
+        ("sourceName", json!("")),
+        ("destinationName", json!("")),
+        ("env", json!({})),
+        ("version", json!(0)),
+    ]
+}
+
+// the fields of QRepConfig as serialized, with their default values
+fn qrep_config_defaults() -> Vec<(&'static str, JsonValue)> {
+    vec![
+        ("flowJobName", json!("")),
+        ("destinationTableIdentifier", json!("")),
+        ("query", json!("")),
+        ("watermarkTable", json!("")),
+        ("watermarkColumn", json!("")),
+        ("initialCopyOnly", json!(false)),
+        ("maxParallelWorkers", json!(0)),
+        ("waitBetweenBatchesSeconds", json!(0)),
+        (
+            "writeMode",
+            json!({
+                "writeType": QRepWriteType::default().as_str_name(),
+                "upsertKeyColumns": [],
+            }),
+        ),
+        ("stagingPath", json!("")),
+        ("numRowsPerPartition", json!(0)),
+        ("setupWatermarkTableOnDestination", json!(false)),
+        ("dstTableFullResync", json!(false)),
+        ("syncedAtColName", json!("")),
+        ("softDeleteColName", json!("")),
+        ("system", json!(TypeSystem::default().as_str_name())),
+        ("script", json!("")),
+        ("sourceName", json!("")),
+        ("destinationName", json!("")),
+        ("snapshotName", json!("")),
+        ("env", json!({})),
+        ("parentMirrorName", json!("")),
+        ("exclude", json!([])),
+        ("columns", json!([])),
+        ("version", json!(0)),
+    ]
+}
+
+// STATUS_RUNNING is shown as running
+fn status_name(status: FlowStatus) -> String {
+    status
+        .as_str_name()
+        .trim_start_matches("STATUS_")
+        .to_lowercase()
+}
+
+fn redacted_options(peer: &Peer) -> anyhow::Result<JsonValue> {
+    let mut peer = peer.clone();
+    if let Some(config) = &mut peer.config {
+        redact_config(config);
+    }
+    // the config is serialized next to the name and type, keyed by the kind of peer
+    let JsonValue::Object(mut fields) = serde_json::to_value(&peer)? else {
+        return Ok(JsonValue::Null);
+    };
+    fields.remove("name");
+    fields.remove("type");
+    Ok(fields
+        .into_iter()
+        .next()
+        .map_or(JsonValue::Null, |(_, config)| config))
+}
+
+// the fields marked with `peerdb_redacted` in peers.proto
+fn redact_config(config: &mut Config) {
+    match config {
+        Config::SnowflakeConfig(c) => {
+            redact_field(&mut c.private_key);
+            redact_optional_field(&mut c.password);
+        }
+        Config::BigqueryConfig(c) => {
+            redact_field(&mut c.private_key_id);
+            redact_field(&mut c.private_key);
+        }
+        Config::MongoConfig(c) => {
+            redact_field(&mut c.password);
+            redact_optional_field(&mut c.root_ca);
+        }
+        Config::PostgresConfig(c) => {
+            redact_field(&mut c.password);
+            redact_optional_field(&mut c.root_ca);
+            if let Some(config) = &mut c.ssh_config {
+                redact_ssh_config(config);
+            }
+            if let Some(config) = &mut c.aws_auth {
+                redact_aws_auth(config);
+            }
+        }
+        Config::S3Config(c) => redact_s3_config(c),
+        Config::SqlserverConfig(c) => redact_field(&mut c.password),
+        Config::EventhubGroupConfig(c) => {
+            for eventhub in c.eventhubs.values_mut() {
+                redact_field(&mut eventhub.subscription_id);
+            }
+        }
+        Config::ClickhouseConfig(c) => {
+            redact_field(&mut c.password);
+            redact_field(&mut c.access_key_id);
+            redact_field(&mut c.secret_access_key);
+            redact_optional_field(&mut c.certificate);
+            redact_optional_field(&mut c.private_key);
+            redact_optional_field(&mut c.root_ca);
+            if let Some(config) = &mut c.s3 {
+                redact_s3_config(config);
+            }
+        }
+        Config::KafkaConfig(c) => redact_field(&mut c.password),
+        Config::PubsubConfig(c) => {
+            if let Some(service_account) = &mut c.service_account {
+                redact_field(&mut service_account.private_key_id);
+                redact_field(&mut service_account.private_key);
+            }
+        }
+        Config::ElasticsearchConfig(c) => {
+            redact_optional_field(&mut c.password);
+            redact_optional_field(&mut c.api_key);
+        }
+        Config::MysqlConfig(c) => {
+            redact_field(&mut c.password);
+            redact_optional_field(&mut c.root_ca);
+            if let Some(config) = &mut c.ssh_config {
+                redact_ssh_config(config);
+            }
+            if let Some(config) = &mut c.aws_auth {
+                redact_aws_auth(config);
+            }
+        }
+    }
+}
+
+fn redact_ssh_config(config: &mut SshConfig) {
+    redact_field(&mut config.password);
+    redact_field(&mut config.private_key);
+    redact_field(&mut config.host_key);
+}
+
+fn redact_aws_auth(config: &mut AwsAuthenticationConfig) {
+    if let Some(AuthConfig::StaticCredentials(credentials)) = &mut config.auth_config {
+        redact_field(&mut credentials.access_key_id);
+        redact_field(&mut credentials.secret_access_key);
+    }
+}
+
+fn redact_s3_config(config: &mut S3Config) {
+    redact_optional_field(&mut config.access_key_id);
+    redact_optional_field(&mut config.secret_access_key);
+    redact_optional_field(&mut config.root_ca);
+}
+
+// empty fields are left as they are, there is nothing to hide
+fn redact_field(field: &mut String) {
+    if !field.is_empty() {
+        *field = REDACTED.to_owned();
+    }
+}
+
+fn redact_optional_field(field: &mut Option<String>) {
+    if let Some(field) = field {
+        redact_field(field);
+    }
+}
+
+fn redact_alert_config(service_type: &str, service_config: &mut JsonValue) {
+    let secrets = ALERT_SECRET_OPTIONS
+        .iter()
+        .find(|(service, _)| *service == service_type)
+        .map_or(&[][..], |(_, secrets)| *secrets);
+    if let JsonValue::Object(fields) = service_config {
+        for secret in secrets {
+            if let Some(field) = fields.get_mut(*secret) {
+                *field = JsonValue::String(REDACTED.to_owned());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pt::peerdb_peers::{
+        AwsAuthStaticCredentialsConfig, BigqueryConfig, ClickhouseConfig, ElasticsearchConfig,
+        EventHubConfig, EventHubGroupConfig, GcpServiceAccount, KafkaConfig, MongoConfig,
+        MySqlConfig, PostgresConfig, PubSubConfig, SnowflakeConfig, SqlServerConfig,
+    };
+
+    use super::*;
+
+    // every credential below holds `hunter2`, which no redacted config may contain
+    fn secret(name: &str) -> String {
+        format!("hunter2-{name}")
+    }
+
+    fn ssh_config() -> Option<SshConfig> {
+        Some(SshConfig {
+            host: "bastion".to_owned(),
+            port: 22,
+            user: "ssh_user".to_owned(),
+            password: secret("ssh-password"),
+            private_key: secret("ssh-private-key"),
+            host_key: secret("ssh-host-key"),
+        })
+    }
+
+    fn aws_auth() -> Option<AwsAuthenticationConfig> {
+        Some(AwsAuthenticationConfig {
+            region: "us-east-1".to_owned(),
+            auth_config: Some(AuthConfig::StaticCredentials(
+                AwsAuthStaticCredentialsConfig {
+                    access_key_id: secret("aws-access-key-id"),
+                    secret_access_key: secret("aws-secret-access-key"),
+                },
+            )),
+            ..Default::default()
+        })
+    }
+
+    fn s3_config() -> S3Config {
+        S3Config {
+            url: "s3://bucket/prefix".to_owned(),
+            access_key_id: Some(secret("s3-access-key-id")),
+            secret_access_key: Some(secret("s3-secret-access-key")),
+            root_ca: Some(secret("s3-root-ca")),
+            ..Default::default()
+        }
+    }
+
+    // one config of each kind, with every field marked `peerdb_redacted` set
+    fn configs() -> Vec<Config> {
+        vec![
+            Config::SnowflakeConfig(SnowflakeConfig {
+                account_id: "account".to_owned(),
+                private_key: secret("snowflake-private-key"),
+                password: Some(secret("snowflake-password")),
+                ..Default::default()
+            }),
+            Config::BigqueryConfig(BigqueryConfig {
+                project_id: "project".to_owned(),
+                private_key_id: secret("bigquery-private-key-id"),
+                private_key: secret("bigquery-private-key"),
+                ..Default::default()
+            }),
+            Config::MongoConfig(MongoConfig {
+                uri: "mongodb://mongo:27017".to_owned(),
+                password: secret("mongo-password"),
+                root_ca: Some(secret("mongo-root-ca")),
+                ..Default::default()
+            }),
+            Config::PostgresConfig(PostgresConfig {
+                host: "postgres".to_owned(),
+                password: secret("postgres-password"),
+                root_ca: Some(secret("postgres-root-ca")),
+                ssh_config: ssh_config(),
+                aws_auth: aws_auth(),
+                ..Default::default()
+            }),
+            Config::S3Config(s3_config()),
+            Config::SqlserverConfig(SqlServerConfig {
+                server: "sqlserver".to_owned(),
+                password: secret("sqlserver-password"),
+                ..Default::default()
+            }),
+            Config::EventhubGroupConfig(EventHubGroupConfig {
+                eventhubs: HashMap::from([(
+                    "namespace".to_owned(),
+                    EventHubConfig {
+                        namespace: "namespace".to_owned(),
+                        subscription_id: secret("eventhub-subscription-id"),
+                        ..Default::default()
+                    },
+                )]),
+                ..Default::default()
+            }),
+            Config::ClickhouseConfig(ClickhouseConfig {
+                host: "clickhouse".to_owned(),
+                password: secret("clickhouse-password"),
+                access_key_id: secret("clickhouse-access-key-id"),
+                secret_access_key: secret("clickhouse-secret-access-key"),
+                certificate: Some(secret("clickhouse-certificate")),
+                private_key: Some(secret("clickhouse-private-key")),
+                root_ca: Some(secret("clickhouse-root-ca")),
+                s3: Some(s3_config()),
+                ..Default::default()
+            }),
+            Config::KafkaConfig(KafkaConfig {
+                servers: vec!["kafka:9092".to_owned()],
+                password: secret("kafka-password"),
+                ..Default::default()
+            }),
+            Config::PubsubConfig(PubSubConfig {
+                service_account: Some(GcpServiceAccount {
+                    project_id: "project".to_owned(),
+                    private_key_id: secret("pubsub-private-key-id"),
+                    private_key: secret("pubsub-private-key"),
+                    ..Default::default()
+                }),
+            }),
+            Config::ElasticsearchConfig(ElasticsearchConfig {
+                addresses: vec!["http://elasticsearch:9200".to_owned()],
+                password: Some(secret("elasticsearch-password")),
+                api_key: Some(secret("elasticsearch-api-key")),
+                ..Default::default()
+            }),
+            Config::MysqlConfig(MySqlConfig {
+                host: "mysql".to_owned(),
+                password: secret("mysql-password"),
+                root_ca: Some(secret("mysql-root-ca")),
+                ssh_config: ssh_config(),
+                aws_auth: aws_auth(),
+                ..Default::default()
+            }),
+        ]
+    }
+
+    #[test]
+    fn peer_credentials_are_redacted() {
+        for config in configs() {
+            let peer = Peer {
+                name: "peer".to_owned(),
+                config: Some(config),
+                ..Default::default()
+            };
+            let options = redacted_options(&peer).unwrap();
+            assert!(options.is_object(), "{peer:?} has no options");
+            let options = options.to_string();
+            assert!(!options.contains("hunter2"), "{options} holds a secret");
+            assert!(options.contains(REDACTED), "{options} is not redacted");
+        }
+    }
+
+    #[test]
+    fn empty_credentials_are_kept() {
+        let peer = Peer {
+            name: "peer".to_owned(),
+            config: Some(Config::PostgresConfig(PostgresConfig {
+                host: "postgres".to_owned(),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        let options = redacted_options(&peer).unwrap();
+        assert_eq!(options["host"], "postgres");
+        assert!(!options.to_string().contains(REDACTED));
+    }
+
+    #[test]
+    fn alert_credentials_are_redacted() {
+        let mut slack = json!({
+            "auth_token": "xoxb-hunter2",
+            "channel_ids": ["C0123"],
+        });
+        redact_alert_config("slack", &mut slack);
+        assert_eq!(
+            slack,
+            json!({"auth_token": REDACTED, "channel_ids": ["C0123"]})
+        );
+
+        let email = json!({"email_addresses": ["ops@example.com"]});
+        let mut redacted = email.clone();
+        redact_alert_config("email", &mut redacted);
+        assert_eq!(redacted, email);
+    }
+
+    #[test]
+    fn alerts_records_redact_credentials() {
+        let records = alerts_records(vec![AlertConfig {
+            id: 1,
+            service_type: "slack".to_owned(),
+            service_config: r#"{"auth_token": "xoxb-hunter2"}"#.to_owned(),
+            alert_for_mirrors: vec![],
+        }])
+        .unwrap();
+        let Value::Text(config) = &records.records[0].values[2] else {
+            panic!("config is not text");
+        };
+        assert!(!config.contains("hunter2"));
+        assert!(config.contains(REDACTED));
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+
+use analyzer::{PeerDDL, PeerDDLAnalyzer, StatementAnalyzer};
+use catalog::Catalog;
+use flow_rs::grpc::{self, FlowGrpcClient};
+use pt::{
+    flow_model::FlowJobTableMapping, peerdb_flow::DynconfValueType, peerdb_route::DynamicSetting,
+};
+use sqlparser::ast::{CreateMirror, Statement};
+
+// the spellings accepted by go's strconv.ParseBool
+const GO_BOOLS: [&str; 12] = [
+    "1", "t", "T", "TRUE", "true", "True", "0", "f", "F", "FALSE", "false", "False",
+];
+
+/// A problem with a definition, next to the name of the peer, mirror or table it is about.
+pub type Problem = (String, String);
+
+/// Checks a `CREATE PEER` statement the way creating the peer would, without creating it.
+pub async fn validate_peer(
+    flow_handler: &mut FlowGrpcClient,
+    create_peer: &Statement,
+) -> anyhow::Result<Vec<Problem>> {
+    let peer = match PeerDDLAnalyzer.analyze(create_peer) {
+        Ok(Some(PeerDDL::CreatePeer { peer, .. })) => peer,
+        Ok(_) => anyhow::bail!("expected a CREATE PEER statement"),
+        Err(err) => return Ok(vec![(statement_name(create_peer), format!("{err:#}"))]),
+    };
+
+    let mut problems = Vec::new();
+    if let Some(message) = flow_handler.validate_peer(&peer).await? {
+        problems.push((peer.name.clone(), message));
+    }
+    Ok(problems)
+}
+
+/// Checks a `CREATE MIRROR` statement the way creating the mirror would, without creating it.
+/// All problems are reported, the flow service only gets to check the configs that are complete.
+pub async fn validate_mirror(
+    catalog: &Catalog,
+    flow_handler: &mut FlowGrpcClient,
+    create_mirror: &Statement,
+) -> anyhow::Result<Vec<Problem>> {
+    let ddl = match PeerDDLAnalyzer.analyze(create_mirror) {
+        Ok(Some(ddl)) => ddl,
+        Ok(None) => anyhow::bail!("expected a CREATE MIRROR statement"),
+        Err(err) => return Ok(vec![(statement_name(create_mirror), format!("{err:#}"))]),
+    };
+
+    let mut problems = Vec::new();
+    match ddl {
+        PeerDDL::CreateMirrorForCDC { flow_job, .. } => {
+            check_peers(
+                catalog,
+                &flow_job.source_peer,
+                &flow_job.target_peer,
+                &mut problems,
+            )
+            .await?;
+            for problem in duplicate_tables(mapped_tables(&flow_job.table_mappings)) {
+                problems.push((flow_job.name.clone(), problem));
+            }
+            if let Some(problem) = script_problem(flow_handler, &flow_job.script).await? {
+                problems.push((flow_job.name.clone(), problem));
+            }
+            for problem in env_problems(flow_handler, &flow_job.env).await? {
+                problems.push((flow_job.name.clone(), problem));
+            }
+            match grpc::cdc_flow_config(
+                &flow_job,
+                flow_job.source_peer.clone(),
+                flow_job.target_peer.clone(),
+            ) {
+                Ok(config) if problems.is_empty() => {
+                    if let Some(message) = flow_handler.validate_cdc_mirror(config).await? {
+                        problems.push((flow_job.name.clone(), message));
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => problems.push((flow_job.name.clone(), format!("{err:#}"))),
+            }
+        }
+        PeerDDL::CreateMirrorForSelect { qrep_flow_job, .. } => {
+            check_peers(
+                catalog,
+                &qrep_flow_job.source_peer,
+                &qrep_flow_job.target_peer,
+                &mut problems,
+            )
+            .await?;
+            if let Err(err) = grpc::qrep_config(
+                &qrep_flow_job,
+                qrep_flow_job.source_peer.clone(),
+                qrep_flow_job.target_peer.clone(),
+            ) {
+                problems.push((qrep_flow_job.name.clone(), format!("{err:#}")));
+            }
+        }
+        _ => anyhow::bail!("expected a CREATE MIRROR statement"),
+    }
+    Ok(problems)
+}
+
+/// Source and destination tables which are mapped more than once, given the
+/// `(source, destination)` of every table mapping.
+pub fn duplicate_tables<'a>(
+    table_mappings: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut sources = HashSet::new();
+    let mut destinations = HashSet::new();
+    for (source, destination) in table_mappings {
+        if !sources.insert(source) {
+            problems.push(format!("Duplicate source table identifier {source}"));
+        }
+        if !destinations.insert(destination) {
+            problems.push(format!(
+                "Duplicate destination table identifier {destination}"
+            ));
+        }
+    }
+    problems
+}
+
+/// The `(source, destination)` of each table mapping, as [`duplicate_tables`] takes them.
+pub fn mapped_tables(table_mappings: &[FlowJobTableMapping]) -> impl Iterator<Item = (&str, &str)> {
+    table_mappings.iter().map(|tm| {
+        (
+            tm.source_table_identifier.as_str(),
+            tm.destination_table_identifier.as_str(),
+        )
+    })
+}
+
+/// Checks that the script a mirror refers to exists, mirrors without a script have no problem.
+pub async fn script_problem(
+    flow_handler: &mut FlowGrpcClient,
+    script: &str,
+) -> anyhow::Result<Option<String>> {
+    if script.is_empty() {
+        return Ok(None);
+    }
+    let scripts = flow_handler.scripts().await?;
+    if scripts.iter().any(|existing| existing.name == script) {
+        Ok(None)
+    } else {
+        Ok(Some(format!("script {script} does not exist")))
+    }
+}
+
+/// Checks the dynamic settings a mirror overrides, each has to be a known setting and its
+/// value has to be valid for it.
+pub async fn env_problems(
+    flow_handler: &mut FlowGrpcClient,
+    env: &HashMap<String, String>,
+) -> anyhow::Result<Vec<String>> {
+    if env.is_empty() {
+        return Ok(Vec::new());
+    }
+    let settings = flow_handler.dynamic_settings().await?;
+    Ok(env_setting_problems(&settings, env))
+}
+
+// the problems of env_problems, given the settings the flow service knows
+fn env_setting_problems(settings: &[DynamicSetting], env: &HashMap<String, String>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (name, value) in env {
+        match settings.iter().find(|setting| setting.name == *name) {
+            Some(setting) => problems.extend(setting_problem(setting, value)),
+            None => problems.push(format!("unrecognized setting {name}")),
+        }
+    }
+    problems.sort();
+    problems
+}
+
+/// Checks a value for a dynamic setting the way the flow service will read it.
+pub fn setting_problem(setting: &DynamicSetting, value: &str) -> Option<String> {
+    let valid = match DynconfValueType::try_from(setting.value_type) {
+        Ok(DynconfValueType::Int) => value.parse::<i64>().is_ok(),
+        Ok(DynconfValueType::Uint) => value.parse::<u64>().is_ok(),
+        Ok(DynconfValueType::Bool) => GO_BOOLS.contains(&value),
+        _ => true,
+    };
+    if valid {
+        None
+    } else {
+        let value_type = DynconfValueType::try_from(setting.value_type)
+            .map_or("UNKNOWN", |value_type| value_type.as_str_name());
+        Some(format!(
+            "invalid value {value} for setting {}, expected {}",
+            setting.name,
+            value_type.to_lowercase()
+        ))
+    }
+}
+
+async fn check_peers(
+    catalog: &Catalog,
+    source_peer: &str,
+    target_peer: &str,
+    problems: &mut Vec<Problem>,
+) -> anyhow::Result<()> {
+    for peer_name in [source_peer, target_peer] {
+        if catalog.check_peer_entry(peer_name).await? == 0 {
+            problems.push((peer_name.to_owned(), "no such peer".to_owned()));
+        }
+    }
+    Ok(())
+}
+
+// names the definition when it could not be analyzed
+fn statement_name(stmt: &Statement) -> String {
+    match stmt {
+        Statement::CreatePeer { peer_name, .. } => peer_name.to_string(),
+        Statement::CreateMirror { create_mirror, .. } => match create_mirror {
+            CreateMirror::CDC(cdc) => cdc.mirror_name.to_string(),
+            CreateMirror::Select(select) => select.mirror_name.to_string(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setting(value_type: DynconfValueType) -> DynamicSetting {
+        named_setting("PEERDB_SETTING", value_type)
+    }
+
+    fn named_setting(name: &str, value_type: DynconfValueType) -> DynamicSetting {
+        DynamicSetting {
+            name: name.to_owned(),
+            value_type: value_type as i32,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn int_setting() {
+        let int = setting(DynconfValueType::Int);
+        for value in ["0", "-5", "9223372036854775807"] {
+            assert_eq!(setting_problem(&int, value), None, "{value}");
+        }
+        for value in ["", "1.5", "ten", "9223372036854775808"] {
+            assert!(setting_problem(&int, value).is_some(), "{value}");
+        }
+    }
+
+    #[test]
+    fn uint_setting() {
+        let uint = setting(DynconfValueType::Uint);
+        assert_eq!(setting_problem(&uint, "18446744073709551615"), None);
+        assert_eq!(
+            setting_problem(&uint, "-1").as_deref(),
+            Some("invalid value -1 for setting PEERDB_SETTING, expected uint")
+        );
+    }
+
+    #[test]
+    fn bool_setting() {
+        let boolean = setting(DynconfValueType::Bool);
+        for value in GO_BOOLS {
+            assert_eq!(setting_problem(&boolean, value), None, "{value}");
+        }
+        for value in ["yes", "TrUe", "2", ""] {
+            assert!(setting_problem(&boolean, value).is_some(), "{value}");
+        }
+    }
+
+    #[test]
+    fn string_setting() {
+        let string = setting(DynconfValueType::String);
+        assert_eq!(setting_problem(&string, "anything"), None);
+        assert_eq!(setting_problem(&string, ""), None);
+    }
+
+    fn env(settings: &[(&str, &str)]) -> HashMap<String, String> {
+        settings
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn env_settings() {
+        let settings = [
+            named_setting("PEERDB_CDC_CHANNEL_BUFFER_SIZE", DynconfValueType::Int),
+            named_setting("PEERDB_QUEUE_FORCE_TOPIC_CREATION", DynconfValueType::Bool),
+            named_setting("PEERDB_CLICKHOUSE_BINARY_FORMAT", DynconfValueType::String),
+        ];
+        let valid = env(&[
+            ("PEERDB_CDC_CHANNEL_BUFFER_SIZE", "-1"),
+            ("PEERDB_QUEUE_FORCE_TOPIC_CREATION", "t"),
+            ("PEERDB_CLICKHOUSE_BINARY_FORMAT", "base64"),
+        ]);
+        assert!(env_setting_problems(&settings, &valid).is_empty());
+        assert!(env_setting_problems(&settings, &HashMap::new()).is_empty());
+
+        let invalid = env(&[
+            ("PEERDB_CDC_CHANNEL_BUFFER_SIZE", "big"),
+            ("PEERDB_QUEUE_FORCE_TOPIC_CREATION", "yes"),
+            ("PEERDB_NO_SUCH_SETTING", "1"),
+            // names are matched exactly, as the flow service reads them
+            ("peerdb_cdc_channel_buffer_size", "1"),
+        ]);
+        assert_eq!(
+            env_setting_problems(&settings, &invalid),
+            [
+                "invalid value big for setting PEERDB_CDC_CHANNEL_BUFFER_SIZE, expected int",
+                "invalid value yes for setting PEERDB_QUEUE_FORCE_TOPIC_CREATION, expected bool",
+                "unrecognized setting PEERDB_NO_SUCH_SETTING",
+                "unrecognized setting peerdb_cdc_channel_buffer_size",
+            ]
+        );
+    }
+}
@@ -7,7 +7,7 @@ use std::{
     time::Duration,
 };
 
-use postgres::{Client, NoTls, SimpleQueryMessage};
+use postgres::{Client, NoTls, SimpleQueryMessage, error::SqlState};
 use similar::TextDiff;
 
 mod create_peers;
@@ -65,8 +65,13 @@ impl PeerDBServer {
     }
 
     fn connect_dying(&self) -> Client {
-        let connection_string = "host=localhost port=9900 password=peerdb user=peerdb";
-        let mut client_result = Client::connect(connection_string, NoTls);
+        self.connect_as("peerdb", "peerdb")
+            .expect("Failed to connect to server.")
+    }
+
+    fn connect_as(&self, user: &str, password: &str) -> Result<Client, postgres::Error> {
+        let connection_string = format!("host=localhost port=9900 password={password} user={user}");
+        let mut client_result = Client::connect(&connection_string, NoTls);
 
         let mut client_established = false;
         let max_attempts = 10;
@@ -76,24 +81,24 @@ impl PeerDBServer {
                 Ok(_) => {
                     client_established = true;
                 }
+                // the server is up but refused the login, retrying won't help
+                Err(ref err) if err.code().is_some() => break,
                 Err(_) => {
                     attempts += 1;
                     thread::sleep(Duration::from_millis(2000 * attempts));
-                    client_result = Client::connect(connection_string, NoTls);
+                    client_result = Client::connect(&connection_string, NoTls);
                 }
             }
         }
 
-        match client_result {
-            Ok(c) => c,
-            Err(_e) => {
-                tracing::info!(
-                    "unable to connect to server after {} attempts",
-                    max_attempts
-                );
-                panic!("Failed to connect to server.");
-            }
+        if client_result.is_err() {
+            tracing::info!(
+                "unable to connect to server as {} after {} attempts",
+                user,
+                attempts
+            );
         }
+        client_result
     }
 }
 
@@ -234,3 +239,108 @@ fn extended_query_protocol_no_params_bq() {
     // check that the result is non-empty.
     assert!(res > 0);
 }
+
+fn count_pg_test_rows(client: &mut Client) -> String {
+    let res = client
+        .simple_query("SELECT COUNT(*) FROM pg_test.test.test_table;")
+        .expect("Failed to count rows");
+    res.iter()
+        .find_map(|msg| match msg {
+            SimpleQueryMessage::Row(row) => row.get(0).map(String::from),
+            _ => None,
+        })
+        .expect("No rows for count")
+}
+
+#[test]
+#[ignore = "create peers needs flow api"]
+fn transaction_pinned_to_peer() {
+    let server = PeerDBServer::new();
+    let mut client = server.connect_dying();
+    create_peers::create_pg::create(&mut client);
+    let count = count_pg_test_rows(&mut client);
+
+    client.simple_query("BEGIN;").expect("Failed to begin");
+    client
+        .simple_query("DELETE FROM pg_test.test.test_table;")
+        .expect("Failed to delete rows");
+    assert_eq!(count_pg_test_rows(&mut client), "0");
+
+    // the catalog is not part of the transaction pinned to pg_test.
+    let res = client.simple_query("SELECT * FROM peers;");
+    assert!(res.is_err());
+
+    client
+        .simple_query("ROLLBACK;")
+        .expect("Failed to rollback");
+    assert_eq!(count_pg_test_rows(&mut client), count);
+}
+
+#[test]
+fn catalog_statements_rejected_in_transaction() {
+    let server = PeerDBServer::new();
+    let mut client = server.connect_dying();
+
+    // the catalog takes no part in transactions, its statements fail until the block ends.
+    client.simple_query("BEGIN;").expect("Failed to begin");
+    let err = client
+        .simple_query("SELECT * FROM peers;")
+        .expect_err("catalog statement ran inside the transaction");
+    assert_eq!(err.code(), Some(&SqlState::INVALID_TRANSACTION_STATE));
+    client.simple_query("COMMIT;").expect("Failed to commit");
+
+    let res = client.simple_query("SELECT * FROM peers;");
+    assert!(res.is_ok());
+}
+
+#[test]
+fn cancel_request_cancels_running_query() {
+    let server = PeerDBServer::new();
+    let mut client = server.connect_dying();
+
+    let cancel_token = client.cancel_token();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(2000));
+        cancel_token
+            .cancel_query(NoTls)
+            .expect("Failed to send cancel request");
+    });
+
+    // the cancel request should reach the catalog well before the sleep ends.
+    let res = client.simple_query("SELECT pg_sleep(60);");
+    canceller.join().expect("Failed to join canceller");
+    assert!(res.is_err());
+
+    // the session stays usable after the cancelled query.
+    let res = client.simple_query("SELECT * FROM peers;");
+    assert!(res.is_ok());
+}
+
+#[test]
+fn only_admin_manages_users() {
+    let server = PeerDBServer::new();
+    let mut admin = server.connect_dying();
+    admin
+        .simple_query("CREATE USER alice WITH PASSWORD 'secret';")
+        .expect("Failed to create user");
+
+    let mut alice = server
+        .connect_as("alice", "secret")
+        .expect("Failed to connect as the created user");
+    for query in [
+        "CREATE USER mallory WITH PASSWORD 'secret';",
+        "ALTER USER peerdb WITH PASSWORD 'changed';",
+        "DROP USER peerdb;",
+    ] {
+        let err = alice
+            .simple_query(query)
+            .expect_err("user statement ran for a non-admin user");
+        assert_eq!(err.code(), Some(&SqlState::INSUFFICIENT_PRIVILEGE));
+    }
+    drop(alice);
+
+    admin
+        .simple_query("DROP USER alice;")
+        .expect("Failed to drop user");
+    assert!(server.connect_as("alice", "secret").is_err());
+}
@@ -188,6 +188,7 @@ export function reformattedTableMapping(
       engine: row.engine,
       shardingKey: row.shardingKey,
       policyName: row.policyName,
+      softDeleteColName: '',
     }));
 }
 